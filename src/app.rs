@@ -1,12 +1,12 @@
-use std::{collections::HashMap, fs::File, io::{self, Read, Write}, path::PathBuf};
+use std::{cell::Cell, collections::HashMap, fs::{self, File}, io::{self, Read, Write}, path::{Path, PathBuf}, rc::Rc, sync::Mutex, time::{Instant, SystemTime}};
 
+use chrono::NaiveDate;
 use ratatui::{layout::Rect, Frame};
 use serde::{Deserialize, Serialize};
 
-use crate::{json_types::{self, Category, Data, Milestone, Task}, layout_conf::{to_layouts, LayoutNode}, theme::{StyleData, Theme}, timer::Timer, traits::tr_widget::TrWidget, ui::{render_error, render_result, widgets::{variant_id_to_usize, WidgetData}}, wild_type::{Generic, Variant}};
+use crate::{command_line::CommandLine, condition::Condition, command_parser::{self, Arg, Call}, event_bus::{AppEvent, EventBus}, exp_curve::ExpCurve, hooks::HookRunner, json_types::{self, timestamp, Category, Data, ExpEvent, ExpSource, Milestone, Task, TaskStatus}, layout_conf::{to_layouts, LayoutNode}, macros::{self, Macro}, migrations, notifications::{AnnounceMode, NotificationConfig, Notifications, Severity}, schedule::{today, Recurrence}, script::ScriptEngine, storage, theme::{StyleData, Theme}, timer::{PomodoroConfig, Timer}, traits::tr_widget::TrWidget, ui::{notifications::{render_notification_history, render_toasts}, render_command_line, render_timer_claim, widgets::{variant_id_to_usize, WidgetData, WidgetTypes}}, undo::{DataChange, Snapshot}, wild_type::{Generic, Variant}};
 
 pub enum AppCommands{
-    Undefined,
     Quit,
     State(String),
     Toggle(String),
@@ -14,112 +14,285 @@ pub enum AppCommands{
     Resize(String, usize, String),
     Error(String),
     Result(String),
+    Notify(Severity, String),
+    // toggles notification history view
+    Notifications,
+    DismissNotifications,
     Set(String, Variant),
     Change(String, i64),
     Remove(String),
+    // category/task/milestone, position or #unique_id
+    Select(String, String),
     OpenBuffer(String, InputMode),
     CloseBuffer,
     SaveBuffer,
     AddCategory(String),
     AddTask(String, String),
     AddMilestone(String, String),
+    AddExp(String),
+    SetRecurrence(String),
+    EditCategory(String),
+    RemoveCategory,
+    MoveCategory(i32),
+    EditTask(String),
+    RemoveTask,
+    MoveTask(i32),
+    SetTaskExp(String),
+    EditMilestone(String),
+    RemoveMilestone,
+    MoveMilestone(i32),
+    SetMilestoneExp(String),
+    // additional data key, field of selected item
+    Prefill(String, String),
     CompleteTask,
     CompleteMilestone,
     Timer,
+    Pomodoro,
+    PauseTimer,
+    ResumeTimer,
+    Recalculate,
     Save,
+    RestoreBackup(usize),
+    Undo,
+    Redo,
+    // condition, commands run when it's true, commands run otherwise
+    If(Condition, Vec<AppCommands>, Vec<AppCommands>),
+    // script function, arguments
+    Script(String, Vec<Arg>),
 }
 
 impl AppCommands{
-    fn from_str(value: String) -> Self{
-        let value = value.trim();
-        if let Some(split_id) = value.find("("){
-            
-            let name: &str = &value.to_lowercase()[0..split_id];
-            let name = name.trim();
+    /// Commands which can change `App::data`, recorded for undo
+    pub fn mutates_data(&self) -> bool{
+        matches!(self,
+            AppCommands::AddCategory(_) |
+            AppCommands::AddTask(_, _) |
+            AppCommands::AddMilestone(_, _) |
+            AppCommands::AddExp(_) |
+            AppCommands::SetRecurrence(_) |
+            AppCommands::EditCategory(_) |
+            AppCommands::RemoveCategory |
+            AppCommands::MoveCategory(_) |
+            AppCommands::EditTask(_) |
+            AppCommands::RemoveTask |
+            AppCommands::MoveTask(_) |
+            AppCommands::SetTaskExp(_) |
+            AppCommands::EditMilestone(_) |
+            AppCommands::RemoveMilestone |
+            AppCommands::MoveMilestone(_) |
+            AppCommands::SetMilestoneExp(_) |
+            AppCommands::CompleteTask |
+            AppCommands::CompleteMilestone |
+            AppCommands::Script(_, _) |
+            AppCommands::Recalculate |
+            AppCommands::RestoreBackup(_)
+        )
+    }
 
-            let params: Vec<&str> = value[split_id+1..value.len()-1].split(",").collect();
+    /// Names accepted by `from_call`, used for completion
+    pub const NAMES: &'static [&'static str] = &[
+        "Quit", "State", "Toggle", "Resize", "Error", "Result", "Notify", "Notifications", "DismissNotifications", "Set", "Change", "Remove", "Select",
+        "OpenBuffer", "CloseBuffer", "SaveBuffer", "AddCategory", "AddTask", "AddMilestone", "AddExp", "SetRecurrence",
+        "EditCategory", "RemoveCategory", "MoveCategory", "EditTask", "RemoveTask", "MoveTask", "SetTaskExp",
+        "EditMilestone", "RemoveMilestone", "MoveMilestone", "SetMilestoneExp", "Prefill",
+        "CompleteTask", "CompleteMilestone", "Timer", "Pomodoro", "PauseTimer", "ResumeTimer", "Recalculate", "Save", "RestoreBackup", "Undo", "Redo", "If", "Script",
+    ];
 
-            return match name{
-                "toggle" => {
-                    let layout_id = params.get(0).expect("").trim().to_string();
-                    AppCommands::Toggle(layout_id)
-                },
-                "resize" => {
-                    let layout_id = params.get(0).expect("").trim().to_string();
-                    let constraint_id = params.get(1).expect("").trim().parse::<usize>().expect("");
-                    let new_value = params.get(2).expect("").trim().to_string();
-                    AppCommands::Resize(layout_id, constraint_id, new_value)
-                },
-                "state" => {
-                    let state = params.get(0).expect("").trim().to_string();
-                    AppCommands::State(state)
-                },
-                "errot" => {
-                    let error_message = params.get(0).expect("").trim().to_string();
-                    AppCommands::Error(error_message)
-                }
-                "result" => {
-                    let result_message = params.get(0).expect("").trim().to_string();
-                    AppCommands::Result(result_message)
+    /// Command from parsed call, `stack` holds macros it was expanded from
+    pub fn from_call(call: &Call, macros: &HashMap<String, Macro>, stack: &mut Vec<String>) -> Result<Self, String>{
+        let command = match call.key().as_str(){
+            "if" => {
+                if call.args.len() != 2 && call.args.len() != 3{
+                    return Err(format!("`{}` expects 2 or 3 arguments, got {}", call.name, call.args.len()));
                 }
-                "set" => {
-                    let key = params.get(0).expect("").trim().to_string();
-                    let value =  params.get(1).expect("").trim().to_string();
-                    AppCommands::Set(key, Variant::from_string(&value, &Generic::Any))
-                }
-                "change" => {
-                    let key = params.get(0).expect("").trim().to_string();
-                    let value =  params.get(1).expect("").trim().to_string().parse::<i64>().expect("");
-                    AppCommands::Change(key, value)
-                }
-                "remove" => {
-                    let key = params.get(0).expect("").trim().to_string();
-                    AppCommands::Remove(key)
-                }
-                "openbuffer" => {
-                    let name = params.get(0).expect("").trim().to_string();
-                    let mode = InputMode::from_str(params.get(1).expect("").to_string());
-                    AppCommands::OpenBuffer(name, mode)
-                }
-                "addcategory" => {
-                    let name = params.get(0).expect("").trim().to_string();
-                    AppCommands::AddCategory(name)
+                let condition = Condition::from_arg(call.arg(0)?)?;
+                let then_commands = AppCommands::parse_branch(call.arg(1)?, macros, stack)?;
+                let else_commands = match call.args.get(2){
+                    Some(arg) => AppCommands::parse_branch(arg, macros, stack)?,
+                    None => Vec::new(),
+                };
+                AppCommands::If(condition, then_commands, else_commands)
+            }
+            "toggle" => {
+                call.expect_args(1)?;
+                AppCommands::Toggle(call.arg(0)?.text()?)
+            }
+            "resize" => {
+                call.expect_args(3)?;
+                AppCommands::Resize(call.arg(0)?.text()?, call.arg(1)?.parse("constraint index")?, call.arg(2)?.text()?)
+            }
+            "state" => {
+                call.expect_args(1)?;
+                AppCommands::State(call.arg(0)?.text()?)
+            }
+            "error" => {
+                call.expect_args(1)?;
+                AppCommands::Error(call.arg(0)?.text()?)
+            }
+            "result" => {
+                call.expect_args(1)?;
+                AppCommands::Result(call.arg(0)?.text()?)
+            }
+            "notify" => {
+                call.expect_args(2)?;
+                let severity = call.arg(0)?.text()?;
+                let Some(severity) = Severity::from_str(&severity) else {
+                    return Err(format!("unknown severity `{}`, expected info, success, warning or error", severity));
+                };
+                AppCommands::Notify(severity, call.arg(1)?.text()?)
+            }
+            "set" => {
+                call.expect_args(2)?;
+                // Quoted values are always strings, unquoted ones are guessed
+                let value = match call.arg(1)?{
+                    Arg::Str(value) => Variant::Str(value.to_string()),
+                    arg => Variant::from_string(&arg.text()?, &Generic::Any),
+                };
+                AppCommands::Set(call.arg(0)?.text()?, value)
+            }
+            "change" => {
+                call.expect_args(2)?;
+                AppCommands::Change(call.arg(0)?.text()?, call.arg(1)?.parse("integer")?)
+            }
+            "remove" => {
+                call.expect_args(1)?;
+                AppCommands::Remove(call.arg(0)?.text()?)
+            }
+            "select" => {
+                call.expect_args(2)?;
+                AppCommands::Select(call.arg(0)?.text()?, call.arg(1)?.text()?)
+            }
+            "openbuffer" => {
+                call.expect_args(2)?;
+                let mode = call.arg(1)?.text()?;
+                match InputMode::from_str(mode.to_string()){
+                    InputMode::Undefined => return Err(format!("expected input mode `text` or `number`, found `{}`", mode)),
+                    mode => AppCommands::OpenBuffer(call.arg(0)?.text()?, mode),
                 }
-                "addtask" => {
-                    let name = params.get(0).expect("").trim().to_string();
-                    let value =  params.get(1).expect("").trim().to_string();
-                    AppCommands::AddTask(name, value)
+            }
+            "addcategory" => {
+                call.expect_args(1)?;
+                AppCommands::AddCategory(call.arg(0)?.text()?)
+            }
+            "addtask" => {
+                call.expect_args(2)?;
+                AppCommands::AddTask(call.arg(0)?.text()?, call.arg(1)?.text()?)
+            }
+            "addmilestone" => {
+                call.expect_args(2)?;
+                AppCommands::AddMilestone(call.arg(0)?.text()?, call.arg(1)?.text()?)
+            }
+            "addexp" => {
+                call.expect_args(1)?;
+                AppCommands::AddExp(call.arg(0)?.text()?)
+            }
+            "setrecurrence" => {
+                call.expect_args(1)?;
+                AppCommands::SetRecurrence(call.arg(0)?.text()?)
+            }
+            "editcategory" => {
+                call.expect_args(1)?;
+                AppCommands::EditCategory(call.arg(0)?.text()?)
+            }
+            "movecategory" => {
+                call.expect_args(1)?;
+                AppCommands::MoveCategory(call.arg(0)?.parse("integer")?)
+            }
+            "edittask" => {
+                call.expect_args(1)?;
+                AppCommands::EditTask(call.arg(0)?.text()?)
+            }
+            "movetask" => {
+                call.expect_args(1)?;
+                AppCommands::MoveTask(call.arg(0)?.parse("integer")?)
+            }
+            "settaskexp" => {
+                call.expect_args(1)?;
+                AppCommands::SetTaskExp(call.arg(0)?.text()?)
+            }
+            "editmilestone" => {
+                call.expect_args(1)?;
+                AppCommands::EditMilestone(call.arg(0)?.text()?)
+            }
+            "movemilestone" => {
+                call.expect_args(1)?;
+                AppCommands::MoveMilestone(call.arg(0)?.parse("integer")?)
+            }
+            "setmilestoneexp" => {
+                call.expect_args(1)?;
+                AppCommands::SetMilestoneExp(call.arg(0)?.text()?)
+            }
+            "prefill" => {
+                call.expect_args(2)?;
+                AppCommands::Prefill(call.arg(0)?.text()?, call.arg(1)?.text()?)
+            }
+            "script" => {
+                if call.args.is_empty(){
+                    return Err(format!("`{}` expects function name", call.name));
                 }
-                "addmilestone" => {
-                    let name = params.get(0).expect("").trim().to_string();
-                    let value =  params.get(1).expect("").trim().to_string();
-                    AppCommands::AddMilestone(name, value)
+                let args = call.args[1..].to_vec();
+                if let Some(Arg::Call(nested)) = args.iter().find(|arg| matches!(arg, Arg::Call(_))){
+                    return Err(format!("expected value, found command `{}`", nested.name));
                 }
-                "timer" => AppCommands::Timer,
-                "quit" => AppCommands::Quit,
-                "closebuffer" => AppCommands::CloseBuffer,
-                "savebuffer" => AppCommands::SaveBuffer,
-                "completetask" => AppCommands::CompleteTask,
-                "completemilestone" => AppCommands::CompleteMilestone,
-                "save" => AppCommands::Save,
-                _ => AppCommands::Undefined,
+                AppCommands::Script(call.arg(0)?.text()?, args)
             }
-        }
-        else{
-            let command: &str = &value.trim().to_lowercase();
-            
-            return match command{
-                "timer" => AppCommands::Timer,
-                "quit" => AppCommands::Quit,
-                "closebuffer" => AppCommands::CloseBuffer,
-                "savebuffer" => AppCommands::SaveBuffer,
-                "completetask" => AppCommands::CompleteTask,
-                "completemilestone" => AppCommands::CompleteMilestone,
-                "save" => AppCommands::Save,
-                _ => AppCommands::Undefined,
+            "restorebackup" => {
+                call.expect_args(1)?;
+                AppCommands::RestoreBackup(call.arg(0)?.parse("backup index")?)
             }
+            name => {
+                let command = match name{
+                    "timer" => AppCommands::Timer,
+                    "pomodoro" => AppCommands::Pomodoro,
+                    "pausetimer" => AppCommands::PauseTimer,
+                    "resumetimer" => AppCommands::ResumeTimer,
+                    "quit" => AppCommands::Quit,
+                    "closebuffer" => AppCommands::CloseBuffer,
+                    "savebuffer" => AppCommands::SaveBuffer,
+                    "completetask" => AppCommands::CompleteTask,
+                    "completemilestone" => AppCommands::CompleteMilestone,
+                    "removecategory" => AppCommands::RemoveCategory,
+                    "removetask" => AppCommands::RemoveTask,
+                    "removemilestone" => AppCommands::RemoveMilestone,
+                    "recalculate" => AppCommands::Recalculate,
+                    "save" => AppCommands::Save,
+                    "undo" => AppCommands::Undo,
+                    "redo" => AppCommands::Redo,
+                    "notifications" => AppCommands::Notifications,
+                    "dismissnotifications" => AppCommands::DismissNotifications,
+                    _ => return Err(format!("unknown command `{}`", call.name)),
+                };
+                call.expect_args(0)?;
+                command
+            }
+        };
+        Ok(command)
+    }
+
+    /// Branch of `If`, either single command or quoted `;` separated commands
+    fn parse_branch(arg: &Arg, macros: &HashMap<String, Macro>, stack: &mut Vec<String>) -> Result<Vec<Self>, String>{
+        match arg{
+            Arg::Call(call) => {
+                let calls = macros::expand(vec![call.clone()], macros, stack)?;
+                calls.into_iter().map(|(call, mut stack)| AppCommands::from_call(&call, macros, &mut stack)).collect()
+            }
+            arg => AppCommands::parse_nested(&arg.text()?, macros, stack),
         }
     }
+
+    /// Parses `;` separated commands and expands macros, errors name the failing command
+    pub fn parse(value: &str, macros: &HashMap<String, Macro>) -> Result<Vec<Self>, String>{
+        AppCommands::parse_nested(value, macros, &mut Vec::new())
+    }
+
+    /// `parse` for commands inside macros in `stack`, so macros reached through `If` can't loop
+    fn parse_nested(value: &str, macros: &HashMap<String, Macro>, stack: &mut Vec<String>) -> Result<Vec<Self>, String>{
+        let calls = command_parser::parse(value).map_err(|error| error.to_string())?;
+        let calls = macros::expand(calls, macros, stack)?;
+        calls.into_iter()
+            .map(|(call, mut stack)| AppCommands::from_call(&call, macros, &mut stack).map_err(|error| format!("{} in `{}`", error, call)))
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -150,14 +323,139 @@ pub struct AppConfig{
     pub exp_power: f32,
     #[serde(default = "default_base_exp")]
     pub base_exp: u32,
+    /// Replaces curve built from `exp_power` and `base_exp` when provided
+    #[serde(default)]
+    pub exp_curve: Option<ExpCurve>,
     #[serde(default = "default_timer_frequency")]
     pub timer_frequency: f32,
+    #[serde(default = "default_streak_bonus")]
+    pub streak_bonus: StreakBonus,
+    /// Number of data backups kept next to data.json
+    #[serde(default = "default_backup_count")]
+    pub backup_count: usize,
+    /// Seconds between autosaves, 0 disables autosave
+    #[serde(default = "default_autosave_interval")]
+    pub autosave_interval: f32,
+    /// Number of data changes which can be undone
+    #[serde(default = "default_undo_limit")]
+    pub undo_limit: usize,
+    /// Key opening the command prompt, empty string disables it
+    #[serde(default = "default_command_key")]
+    pub command_key: String,
+    /// Named command sequences callable like commands
+    #[serde(default)]
+    pub macros: HashMap<String, Macro>,
+    /// Script sources defining functions, loaded with `.trs` files from scripts directory
+    #[serde(default)]
+    pub scripts: Vec<String>,
+    /// Listen for JSON-RPC requests on `trexp.sock` in config directory
+    #[serde(default = "default_control_socket")]
+    pub control_socket: bool,
+    /// Shell commands run on app events, by event name
+    #[serde(default)]
+    pub hooks: HashMap<String, Vec<String>>,
+    #[serde(default = "default_notifications")]
+    pub notifications: NotificationConfig,
+    /// Exp for time saved timers ran while app was closed
+    #[serde(default = "default_timer_restore")]
+    pub timer_restore: TimerRestore,
+    #[serde(default = "default_pomodoro")]
+    pub pomodoro: PomodoroConfig,
 }
 pub fn default_exp_power() -> f32{0.85}
 pub fn default_base_exp() -> u32{15}
 pub fn default_timer_frequency() -> f32{15.0}
+pub fn default_streak_bonus() -> StreakBonus{StreakBonus::new()}
+pub fn default_backup_count() -> usize{5}
+pub fn default_autosave_interval() -> f32{60.0}
+pub fn default_undo_limit() -> usize{50}
+pub fn default_command_key() -> String{"C(:)".to_string()}
+pub fn default_control_socket() -> bool{true}
+pub fn default_notifications() -> NotificationConfig{NotificationConfig::new()}
+pub fn default_timer_restore() -> TimerRestore{TimerRestore::Ask}
+pub fn default_pomodoro() -> PomodoroConfig{PomodoroConfig::new()}
+
+/// How time timers ran while app was closed is credited when they are restored
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TimerRestore{
+    Credit,
+    Cap { minutes: u64 },
+    Ask,
+}
+
+/// Exp multiplier for streaks, `bonus` is added for every `step` days of streak, up to `cap`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StreakBonus{
+    pub bonus: f32,
+    pub step: u32,
+    pub cap: f32,
+}
+
+impl StreakBonus{
+    pub fn new() -> Self{
+        Self {
+            bonus: 0.1,
+            step: 7,
+            cap: 0.5,
+        }
+    }
+    pub fn multiplier(&self, streak_days: u32) -> f32{
+        if self.step == 0{
+            return 1.0;
+        }
+        1.0 + (self.bonus * (streak_days / self.step) as f32).min(self.cap)
+    }
+    pub fn apply(&self, exp: u32, streak_days: u32) -> u32{
+        (exp as f32 * self.multiplier(streak_days)).round() as u32
+    }
+}
 
 impl AppConfig{
+    /// Checks macros and parses every keybind command, returning one message per problem
+    pub fn validate(&self) -> Vec<String>{
+        let mut errors: Vec<String> = macros::validate(&self.macros, AppCommands::NAMES);
+        if !errors.is_empty(){
+            // Keybinds calling broken macros would only repeat the same errors
+            return errors;
+        }
+        let mut states: Vec<&String> = self.keybinds.keys().collect();
+        states.sort();
+        for state in states{
+            let mut keys: Vec<(&String, &HelpData)> = self.keybinds[state].iter().collect();
+            keys.sort_by_key(|(key, _)| *key);
+            for (key, help_data) in keys{
+                if let Err(error) = AppCommands::parse(&help_data.command, &self.macros){
+                    errors.push(format!("keybinds.{}.{}: {} (command: `{}`)", state, key, error, help_data.command));
+                }
+            }
+        }
+        let mut events: Vec<&String> = self.hooks.keys().collect();
+        events.sort();
+        for event in events{
+            if !AppEvent::NAMES.contains(&event.as_str()){
+                errors.push(format!("hooks.{}: unknown event, expected one of: {}", event, AppEvent::NAMES.join(", ")));
+            }
+        }
+        // Exp is time divided by these, zero would give endless exp
+        let durations = [
+            ("timer_frequency", self.timer_frequency),
+            ("pomodoro.work_minutes", self.pomodoro.work_minutes),
+            ("pomodoro.short_break_minutes", self.pomodoro.short_break_minutes),
+            ("pomodoro.long_break_minutes", self.pomodoro.long_break_minutes),
+        ];
+        for (name, value) in durations{
+            if !(value > 0.0 && value.is_finite()){
+                errors.push(format!("{}: must be a positive number, got {}", name, value));
+            }
+        }
+        errors
+    }
+    pub fn exp_curve(&self) -> ExpCurve{
+        match &self.exp_curve{
+            Some(curve) => curve.clone(),
+            None => ExpCurve::Power { base: self.base_exp, power: self.exp_power },
+        }
+    }
     pub fn new() -> Self{
         Self {
             layouts: Vec::new(),
@@ -168,7 +466,20 @@ impl AppConfig{
             values: HashMap::new(),
             exp_power: 0.85,
             base_exp: 15,
+            exp_curve: None,
             timer_frequency: 15.0,
+            streak_bonus: StreakBonus::new(),
+            backup_count: 5,
+            autosave_interval: 60.0,
+            undo_limit: 50,
+            command_key: default_command_key(),
+            macros: HashMap::new(),
+            scripts: Vec::new(),
+            control_socket: true,
+            hooks: HashMap::new(),
+            notifications: NotificationConfig::new(),
+            timer_restore: default_timer_restore(),
+            pomodoro: PomodoroConfig::new(),
         }
     }
 }
@@ -206,12 +517,34 @@ pub struct App{
     pub buffer_name: Option<String>,
     pub input_buffer: String,
 
-    pub result_message: String,
-    pub error_message: String,
+    pub notifications: Notifications,
 
     pub additional_data: HashMap<String, Variant>,
 
+    pub command_line: CommandLine,
+
+    pub scripts: Rc<ScriptEngine>,
+    /// Last output of functions shown by script widgets
+    pub script_output: HashMap<String, String>,
+    script_depth: usize,
+
+    pub event_bus: EventBus,
+    hooks: HookRunner,
+
     pub config_path: PathBuf,
+    /// Set when existing data file couldn't be loaded, blocks saving over it
+    pub data_load_error: Option<String>,
+    /// Modification time of data file when it was last read or written, changes made since
+    /// by other programs aren't overwritten
+    data_modified: Cell<Option<SystemTime>>,
+    /// Set when data or timers may have changed since crash snapshot was taken
+    snapshot_stale: bool,
+    last_save: Instant,
+    undo_stack: Vec<DataChange>,
+    redo_stack: Vec<DataChange>,
+    /// Set while a mutating command runs, commands it runs through scripts are part of its change
+    recording_undo: bool,
+    streak_check_day: Option<NaiveDate>,
 }
 
 impl App{
@@ -226,10 +559,23 @@ impl App{
             input_mode: InputMode::Undefined,
             buffer_name: None,
             input_buffer: String::new(),
-            result_message: String::new(),
-            error_message: String::new(),
+            notifications: Notifications::new(),
             additional_data: HashMap::new(),
+            command_line: CommandLine::new(),
+            scripts: Rc::new(ScriptEngine::new()),
+            script_output: HashMap::new(),
+            script_depth: 0,
+            event_bus: EventBus::new(),
+            hooks: HookRunner::new(),
             config_path: PathBuf::new(),
+            data_load_error: None,
+            data_modified: Cell::new(None),
+            snapshot_stale: true,
+            last_save: Instant::now(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            recording_undo: false,
+            streak_check_day: None,
         }
     }
     pub fn init(&mut self, config_path: PathBuf){
@@ -237,15 +583,145 @@ impl App{
             self.additional_data.insert(key, Variant::from_string(&value, &Generic::Any));
         }
         self.config_path = config_path;
-        let _ = self.load_data();
+        match self.load_data(){
+            Ok(()) => {},
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {},
+            Err(error) => {
+                self.notify(Severity::Error, format!("Couldn't load data.json, saving is disabled to protect it: {}", error));
+                self.data_load_error = Some(error.to_string());
+            }
+        }
+        self.sync_exp_curve();
+        self.restore_timers();
+        self.load_scripts();
+    }
+
+    pub fn load_scripts(&mut self){
+        let mut engine = ScriptEngine::new();
+        let mut errors: Vec<String> = Vec::new();
+
+        for (id, source) in self.app_config.scripts.iter().enumerate(){
+            if let Err(error) = engine.load(&format!("scripts.{}", id), source){
+                errors.push(error);
+            }
+        }
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(self.config_path.join("scripts"))
+            .map(|entries| entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect())
+            .unwrap_or_default();
+        paths.retain(|path| path.extension().is_some_and(|extension| extension == "trs"));
+        paths.sort();
+        for path in paths{
+            let origin = path.file_name().and_then(|name| name.to_str()).unwrap_or("script").to_string();
+            let result = fs::read_to_string(&path)
+                .map_err(|error| format!("{}: {}", origin, error))
+                .and_then(|source| engine.load(&origin, &source));
+            if let Err(error) = result{
+                errors.push(error);
+            }
+        }
+
+        self.scripts = Rc::new(engine);
+        for error in errors{
+            self.notify(Severity::Error, format!("Couldn't load scripts: {}", error));
+        }
+    }
+
+    /// Calls script function, scripts running commands can call other scripts up to a limit
+    pub fn run_script(&mut self, name: &str, args: Vec<Variant>) -> Result<Variant, String>{
+        if self.script_depth >= 8{
+            return Err("scripts nested too deep".to_string());
+        }
+        let engine = Rc::clone(&self.scripts);
+        self.script_depth += 1;
+        let result = engine.call(self, name, args);
+        self.script_depth -= 1;
+        result
+    }
+
+    /// Calls function shown by a script widget, it runs every frame so it can only read the app
+    pub fn run_widget_script(&mut self, name: &str) -> Result<Variant, String>{
+        let engine = Rc::clone(&self.scripts);
+        engine.call_read_only(self, name, Vec::new())
+    }
+
+    fn script_arg(&self, arg: &Arg) -> Result<Variant, String>{
+        match arg{
+            Arg::Str(value) => Ok(Variant::Str(value.to_string())),
+            Arg::Raw(value) => {
+                let Some(name) = value.strip_prefix('$') else {
+                    return Ok(Variant::from_string(value, &Generic::Any));
+                };
+                if Some(name.to_string()) == self.buffer_name{
+                    return Ok(Variant::from_string(&self.input_buffer, &Generic::Any));
+                }
+                self.additional_data.get(name).cloned().ok_or_else(|| format!("variable `{}` is not set", name))
+            }
+            Arg::Call(call) => Err(format!("expected value, found command `{}`", call.name)),
+        }
+    }
+
+    /// Recalculates levels when exp curve changed since data was saved
+    pub fn sync_exp_curve(&mut self){
+        let curve = self.app_config.exp_curve();
+        if self.data.curve_fingerprint != curve.fingerprint(){
+            self.data.recalculate(&curve);
+        }
     }
 
     pub fn run_command_string(&mut self, commands: String){
-        for command in commands.split(';'){
-            self.run_command(&AppCommands::from_str(command.to_string()));
+        match AppCommands::parse(&commands, &self.app_config.macros){
+            Ok(commands) => {
+                for command in commands.iter(){
+                    self.run_command(command);
+                }
+            }
+            Err(error) => {
+                self.notify(Severity::Error, format!("Invalid command: {}", error));
+            }
         }
     }
+    /// Runs command typed in the prompt, keeping the prompt open with an error when it doesn't parse
+    pub fn submit_command_line(&mut self){
+        let input = self.command_line.input.trim().to_string();
+        if input.is_empty(){
+            self.command_line.close();
+            return;
+        }
+        match AppCommands::parse(&input, &self.app_config.macros){
+            Ok(commands) => {
+                self.command_line.add_history(input);
+                self.command_line.close();
+                for command in commands.iter(){
+                    self.run_command(command);
+                }
+            }
+            Err(error) => {
+                self.command_line.error = Some(error);
+            }
+        }
+    }
+    pub fn complete_command_line(&mut self){
+        let mut commands: Vec<&str> = AppCommands::NAMES.to_vec();
+        commands.extend(self.app_config.macros.keys().map(|name| name.as_str()));
+        let variables: Vec<&String> = self.additional_data.keys().collect();
+        self.command_line.complete(&commands, variables);
+    }
+
     pub fn run_command(&mut self, command: &AppCommands){
+        self.snapshot_stale = true;
+        if command.mutates_data() && !self.recording_undo{
+            self.recording_undo = true;
+            let snapshot = Snapshot::take(&self.data, matches!(command, AppCommands::RestoreBackup(_)));
+            self.execute_command(command);
+            self.recording_undo = false;
+            self.record_undo(snapshot);
+        }
+        else{
+            self.execute_command(command);
+        }
+    }
+    fn execute_command(&mut self, command: &AppCommands){
         match command{
             AppCommands::Quit => {
                 self.exit = true;
@@ -259,11 +735,20 @@ impl App{
             AppCommands::Resize(layout_id, constraint, new_value) => {
                 self.resize_constraint(layout_id.to_string(), *constraint, new_value.to_string());
             },
-            AppCommands::Error(error_message) => {
-                self.error_message = error_message.to_string();
+            AppCommands::Error(message) => {
+                self.notify(Severity::Error, message.to_string());
+            }
+            AppCommands::Result(message) => {
+                self.notify(Severity::Info, message.to_string());
+            }
+            AppCommands::Notify(severity, message) => {
+                self.notify(*severity, message.to_string());
             }
-            AppCommands::Result(result_message) => {
-                self.result_message = result_message.to_string();
+            AppCommands::Notifications => {
+                self.notifications.toggle_history();
+            }
+            AppCommands::DismissNotifications => {
+                self.notifications.dismiss();
             }
             AppCommands::Set(key, value) => {
                 self.set_data(key.to_string(), value.clone());
@@ -274,6 +759,9 @@ impl App{
             AppCommands::Remove(key) => {
                 self.remove_data(key.to_string());
             }
+            AppCommands::Select(kind, reference) => {
+                self.select(kind.to_string(), reference.to_string());
+            }
             AppCommands::OpenBuffer(name, mode) => {
                 self.open_buffer(name.to_string(), mode.clone());
             }
@@ -292,6 +780,48 @@ impl App{
             AppCommands::AddMilestone(name, value) => {
                 self.add_milestone(name.to_string(), value.to_string());
             }
+            AppCommands::AddExp(value) => {
+                self.add_exp(value.to_string());
+            }
+            AppCommands::SetRecurrence(value) => {
+                self.set_recurrence(value.to_string());
+            }
+            AppCommands::EditCategory(name) => {
+                self.edit_category(name.to_string());
+            }
+            AppCommands::RemoveCategory => {
+                self.remove_category();
+            }
+            AppCommands::MoveCategory(by) => {
+                self.move_category(*by);
+            }
+            AppCommands::EditTask(name) => {
+                self.edit_task(name.to_string());
+            }
+            AppCommands::RemoveTask => {
+                self.remove_task();
+            }
+            AppCommands::MoveTask(by) => {
+                self.move_task(*by);
+            }
+            AppCommands::SetTaskExp(value) => {
+                self.set_task_exp(value.to_string());
+            }
+            AppCommands::EditMilestone(name) => {
+                self.edit_milestone(name.to_string());
+            }
+            AppCommands::RemoveMilestone => {
+                self.remove_milestone();
+            }
+            AppCommands::MoveMilestone(by) => {
+                self.move_milestone(*by);
+            }
+            AppCommands::SetMilestoneExp(value) => {
+                self.set_milestone_exp(value.to_string());
+            }
+            AppCommands::Prefill(key, field) => {
+                self.prefill(key.to_string(), field.to_string());
+            }
             AppCommands::CompleteTask => {
                 self.complete_task();
             }
@@ -299,12 +829,60 @@ impl App{
                 self.complete_milestone();
             }
             AppCommands::Timer => {
-                self.toggle_timer();
+                self.toggle_timer(false);
+            }
+            AppCommands::Pomodoro => {
+                self.toggle_timer(true);
+            }
+            AppCommands::PauseTimer => {
+                self.pause_timer(true);
+            }
+            AppCommands::ResumeTimer => {
+                self.pause_timer(false);
+            }
+            AppCommands::Recalculate => {
+                self.data.recalculate(&self.app_config.exp_curve());
+                self.notify(Severity::Success, "Levels recalculated".to_string());
             }
             AppCommands::Save => {
-                let _ = self.save_data();
+                if let Err(error) = self.save_data(){
+                    self.notify(Severity::Error, format!("Couldn't save data: {}", error));
+                }
+            }
+            AppCommands::RestoreBackup(index) => {
+                match self.restore_backup(*index){
+                    Ok(()) => self.notify(Severity::Success, format!("Backup {} restored", index)),
+                    Err(error) => self.notify(Severity::Error, format!("Couldn't restore backup: {}", error)),
+                }
+            }
+            AppCommands::Undo => {
+                self.undo();
+            }
+            AppCommands::Redo => {
+                self.redo();
+            }
+            AppCommands::Script(name, args) => {
+                let result = args.iter()
+                    .map(|arg| self.script_arg(arg))
+                    .collect::<Result<Vec<Variant>, String>>()
+                    .and_then(|args| self.run_script(name, args));
+                if let Err(error) = result{
+                    self.notify(Severity::Error, format!("Script error: {}", error));
+                }
+            }
+            AppCommands::If(condition, then_commands, else_commands) => {
+                let commands = match condition.evaluate(self){
+                    Ok(true) => then_commands,
+                    Ok(false) => else_commands,
+                    Err(error) => {
+                        self.notify(Severity::Error, format!("Couldn't check condition: {}", error));
+                        return;
+                    }
+                };
+                for command in commands.iter(){
+                    self.run_command(command);
+                }
             }
-            _ => {}
         }
     }
 
@@ -313,38 +891,386 @@ impl App{
         self.state = self.app_config.states.first().expect("No states provided").to_string();
     }
 
-    pub fn load_data(&mut self) -> io::Result<()>{
-        let mut file = File::open(self.config_path.join("data.json").to_str().expect("no config path provided").to_string())?;
+    pub fn data_path(&self) -> PathBuf{
+        self.config_path.join("data.json")
+    }
+
+    /// Reads data file, migrating it from older versions
+    pub fn read_data(path: &Path) -> io::Result<Data>{
+        let mut file = File::open(path)?;
     
         let mut content = String::new();
         file.read_to_string(&mut content)?;
 
-        self.data = serde_json::from_str(&content)?;
+        let value: serde_json::Value = serde_json::from_str(&content)?;
+        let value = migrations::migrate(value).map_err(io::Error::other)?;
+
+        let data: Data = serde_json::from_value(value)?;
 
+        Ok(data)
+    }
+    pub fn load_data(&mut self) -> io::Result<()>{
+        let path = self.data_path();
+        self.data_modified.set(storage::modified(&path));
+        self.data = Self::read_data(&path)?;
+        self.snapshot_stale = true;
         Ok(())
     }
     pub fn save_data(&self) -> io::Result<()>{
-        let data: String = serde_json::to_string(&self.data)?;
+        self.write_data(true)
+    }
+    /// Data with running timers, which are only kept in `timers` while app runs
+    fn data_to_save(&self) -> Data{
+        let mut data = self.data.clone();
+        data.timers = self.timers.iter().map(Timer::record).collect();
+        data
+    }
+    /// Writes data file, `backup` keeps a copy of the previous one
+    pub fn write_data(&self, backup: bool) -> io::Result<()>{
+        if let Some(error) = &self.data_load_error{
+            return Err(io::Error::other(format!("data.json failed to load ({}), refusing to overwrite it", error)));
+        }
+        let path = self.data_path();
+        if storage::modified(&path) != self.data_modified.get(){
+            return Err(io::Error::other("data.json was changed by another program since it was loaded, refusing to overwrite it"));
+        }
+        let data: String = serde_json::to_string(&self.data_to_save())?;
 
-        let mut file = File::create(self.config_path.join("data.json").to_str().expect("no config path provided").to_string())?;
-        file.write_all(data.as_bytes())?;
+        if backup{
+            storage::create_backup(&path, self.app_config.backup_count)?;
+        }
+        storage::write_atomic(&path, data.as_bytes())?;
+        self.data_modified.set(storage::modified(&path));
 
         Ok(())
     }
+    /// Stores what a command changed in data, if anything, with timers of categories it removed
+    fn record_undo(&mut self, snapshot: Snapshot){
+        if !snapshot.history_kept(&self.data){
+            // Backup restored by a script, recorded changes don't apply to restored data
+            self.undo_stack.clear();
+            self.redo_stack.clear();
+            return;
+        }
+        let Some(mut change) = DataChange::between(&snapshot, &self.data) else {
+            return;
+        };
+        change.swap_timers(&self.data, &mut self.timers);
+        self.undo_stack.push(change);
+        if self.undo_stack.len() > self.app_config.undo_limit{
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+    /// Reverts last command, exp and streaks changed since by timers or streak checks are kept
+    pub fn undo(&mut self){
+        let Some(mut change) = self.undo_stack.pop() else {
+            self.notify(Severity::Warning, "Nothing to undo".to_string());
+            return;
+        };
+        change.undo(&mut self.data, &mut self.timers, &self.app_config.exp_curve());
+        self.redo_stack.push(change);
+    }
+    pub fn redo(&mut self){
+        let Some(mut change) = self.redo_stack.pop() else {
+            self.notify(Severity::Warning, "Nothing to redo".to_string());
+            return;
+        };
+        change.redo(&mut self.data, &mut self.timers, &self.app_config.exp_curve());
+        self.undo_stack.push(change);
+    }
+
+    /// Keeps copy of data for panic hook, refreshed when data or running timers changed since last copy
+    pub fn update_crash_snapshot(&mut self, snapshot: &Mutex<Option<Data>>){
+        if !self.snapshot_stale{
+            return;
+        }
+        let Ok(mut snapshot) = snapshot.lock() else {
+            return;
+        };
+        *snapshot = Some(self.data_to_save());
+        self.snapshot_stale = false;
+    }
+    /// Makes next `update_crash_snapshot` take a new copy, for changes made outside of commands
+    pub fn mark_snapshot_stale(&mut self){
+        self.snapshot_stale = true;
+    }
+
+    /// Writes data to a separate file, used when app crashed
+    pub fn write_emergency_snapshot(data_path: &Path, data: &Data) -> io::Result<PathBuf>{
+        let data: String = serde_json::to_string(data)?;
+        storage::write_emergency_snapshot(data_path, data.as_bytes())
+    }
+    pub fn handle_autosave(&mut self){
+        let interval = self.app_config.autosave_interval;
+        if interval <= 0.0 || self.data_load_error.is_some() || self.last_save.elapsed().as_secs_f32() < interval{
+            return;
+        }
+        self.last_save = Instant::now();
+        // Backups are only made on explicit saves, so autosaves don't rotate them out
+        if let Err(error) = self.write_data(false){
+            self.notify(Severity::Error, format!("Autosave failed: {}", error));
+        }
+    }
+    /// Replaces data with backup at `index`, 0 being the newest one
+    pub fn restore_backup(&mut self, index: usize) -> io::Result<()>{
+        let backups = storage::list_backups(&self.data_path())?;
+        let backup = backups.get(index).ok_or_else(|| io::Error::other(format!("no backup with index {}, {} available", index, backups.len())))?;
+
+        let data = Self::read_data(backup)?;
+        // Data which failed to load is kept too, restoring is the way out of it
+        storage::keep_before_restore(&self.data_path())?;
+        self.data_modified.set(storage::modified(&self.data_path()));
+        self.data = data;
+        self.data_load_error = None;
+        self.snapshot_stale = true;
+        self.sync_exp_curve();
+        self.write_data(false)
+    }
+
+    /// Resets streaks which were not continued, once per day
+    pub fn check_streaks(&mut self){
+        let today = today();
+        if self.streak_check_day == Some(today){
+            return;
+        }
+        self.streak_check_day = Some(today);
+        self.snapshot_stale = true;
+
+        let mut broken: Vec<String> = Vec::new();
+        for category in self.data.categories.iter_mut(){
+            if let Some(lost) = category.check_streak(){
+                broken.push(format!("{} ({} days)", category.name, lost));
+            }
+            for task in category.tasks.iter_mut(){
+                if let Some(lost) = task.check_streak(){
+                    broken.push(format!("{} ({} in a row)", task.name, lost));
+                }
+            }
+        }
+
+        if !broken.is_empty(){
+            self.notify(Severity::Warning, format!("Streak broken: {}", broken.join(", ")));
+        }
+    }
 
     pub fn handle_timers(&mut self){
-        for timer in self.timers.iter_mut(){
-            if timer.is_finished(){
-                if let Some(category) = self.data.get_category_uid_mut(timer.category_id){
-                    category.increase_exp(1, self.app_config.exp_power, self.app_config.base_exp);
+        // Pomodoro timers also tick, but get exp for their work phases in `accrue_pomodoro`
+        let finished: Vec<(u64, u32)> = self.timers.iter_mut()
+            .filter_map(|timer| {
+                let exp = timer.finished_intervals();
+                (exp > 0 && timer.pomodoro.is_none()).then_some((timer.category_id, exp))
+            })
+            .collect();
+        for (category_uid, exp) in finished{
+            self.give_exp(category_uid, exp, self.timer_source(exp));
+        }
+        for timer_id in 0..self.timers.len(){
+            if self.timers[timer_id].pomodoro.is_some(){
+                self.accrue_pomodoro(timer_id);
+            }
+        }
+    }
+
+    /// Restarts timers saved in data, time they ran while app was closed is credited by `timer_restore`
+    fn restore_timers(&mut self){
+        let now = timestamp();
+        let mut credited: u32 = 0;
+        for record in std::mem::take(&mut self.data.timers){
+            let Some(category) = self.data.get_category_uid(record.category_uid) else {
+                continue;
+            };
+            let mut timer = Timer::restore(category, self.app_config.timer_frequency, record.started_at);
+            if let Some(pomodoro) = &record.pomodoro{
+                timer.restore_pomodoro(pomodoro, &self.app_config.pomodoro);
+            }
+            // Paused timers weren't running while app was closed
+            if let Some(progress) = record.paused{
+                timer.restore_paused(progress);
+                self.timers.push(timer);
+                continue;
+            }
+            let offline = now.saturating_sub(record.credited_until);
+            let seconds = match self.app_config.timer_restore{
+                TimerRestore::Credit => offline,
+                TimerRestore::Cap { minutes } => offline.min(minutes * 60),
+                TimerRestore::Ask => {
+                    timer.unclaimed = offline;
+                    0
+                }
+            };
+            self.timers.push(timer);
+            credited += self.credit_timer_time(self.timers.len() - 1, seconds);
+        }
+        if credited > 0{
+            self.notify(Severity::Info, format!("Timers kept running while app was closed, gave {} exp", credited));
+        }
+    }
+
+    /// Gives timer exp for `seconds` it ran while app was closed, returns given exp
+    fn credit_timer_time(&mut self, timer_id: usize, seconds: u64) -> u32{
+        let Some(timer) = self.timers.get_mut(timer_id) else {
+            return 0;
+        };
+        // Pomodoro only rewards work phases, so the time is run through its phases
+        if timer.pomodoro.is_some(){
+            timer.advance(seconds as f32);
+            return self.accrue_pomodoro(timer_id);
+        }
+        let category_uid = timer.category_id;
+        let exp = timer.credit(seconds);
+        if exp > 0{
+            self.give_exp(category_uid, exp, self.timer_source(exp));
+        }
+        exp
+    }
+
+    /// Gives pomodoro exp for work time and finished cycles, announcing phase changes. Returns given exp
+    fn accrue_pomodoro(&mut self, timer_id: usize) -> u32{
+        if self.app_config.timer_frequency <= 0.0{
+            return 0;
+        }
+        let config = &self.app_config.pomodoro;
+        let Some(timer) = self.timers.get_mut(timer_id) else {
+            return 0;
+        };
+        let active = timer.active_seconds();
+        let (category_uid, category_name) = (timer.category_id, timer.category_name.to_string());
+        let Some(pomodoro) = timer.pomodoro.as_mut() else {
+            return 0;
+        };
+
+        let earned = (config.work_seconds(active) / self.app_config.timer_frequency) as u32;
+        let exp = earned.saturating_sub(pomodoro.rewarded_exp);
+        pomodoro.rewarded_exp = pomodoro.rewarded_exp.max(earned);
+
+        let cycles = config.finished_cycles(active);
+        let bonus = cycles.saturating_sub(pomodoro.rewarded_cycles) * config.cycle_bonus;
+        pomodoro.rewarded_cycles = pomodoro.rewarded_cycles.max(cycles);
+
+        let (phase, _, left) = config.phase_at(active);
+        let phase_changed = phase != pomodoro.phase;
+        pomodoro.phase = phase;
+        let bell = config.bell;
+
+        if exp > 0{
+            self.give_exp(category_uid, exp, self.timer_source(exp));
+        }
+        if bonus > 0{
+            self.give_exp(category_uid, bonus, ExpSource::PomodoroBonus);
+            self.notify(Severity::Success, format!("{} pomodoro cycle finished, +{} exp", category_name, bonus));
+        }
+        if phase_changed{
+            self.notify(Severity::Info, format!("{}: {} for {} min", category_name, phase, (left / 60.0).ceil()));
+            self.event_bus.publish(AppEvent::PomodoroPhase { category: category_name, category_uid, phase: phase.to_string() });
+            if bell{
+                print!("\x07");
+                let _ = io::stdout().flush();
+            }
+        }
+        exp + bonus
+    }
+
+    /// Seconds restored timers ran while app was closed, not yet credited or discarded
+    pub fn unclaimed_timer_time(&self) -> u64{
+        self.timers.iter().map(|timer| timer.unclaimed).sum()
+    }
+
+    /// Answer to restored timers question, `accept` gives exp for time they ran while app was closed
+    pub fn claim_timer_time(&mut self, accept: bool){
+        let unclaimed: Vec<(usize, u64)> = self.timers.iter_mut()
+            .enumerate()
+            .filter(|(_, timer)| timer.unclaimed > 0)
+            .map(|(timer_id, timer)| (timer_id, std::mem::take(&mut timer.unclaimed)))
+            .collect();
+        if !accept{
+            return;
+        }
+        let mut credited: u32 = 0;
+        for (timer_id, seconds) in unclaimed{
+            credited += self.credit_timer_time(timer_id, seconds);
+        }
+        self.notify(Severity::Success, format!("Gave {} exp for time timers ran while app was closed", credited));
+    }
+
+    /// Gives exp to category with provided uid, publishing level-ups it caused
+    pub fn give_exp(&mut self, category_uid: u64, exp: u32, source: ExpSource) -> Option<ExpEvent>{
+        self.snapshot_stale = true;
+        let user_lvl = self.user_lvl();
+        let event = self.data.increase_exp(category_uid, exp, source, &self.app_config.exp_curve())?.clone();
+
+        if event.lvl_after > event.lvl_before && let Some(category) = self.data.get_category_uid(category_uid){
+            let category = category.name.to_string();
+            self.event_bus.publish(AppEvent::CategoryLevelUp { category, category_uid, lvl_before: event.lvl_before, lvl_after: event.lvl_after });
+        }
+        let lvl_after = self.user_lvl();
+        if lvl_after > user_lvl{
+            self.event_bus.publish(AppEvent::UserLevelUp { lvl_before: user_lvl, lvl_after });
+        }
+        Some(event)
+    }
+
+    /// Source of `exp` given by timers, with time it took to earn
+    fn timer_source(&self, exp: u32) -> ExpSource{
+        ExpSource::Timer { seconds: (exp as f64 * self.app_config.timer_frequency as f64).round() as u64 }
+    }
+
+    /// Level of all categories exp summed
+    pub fn user_lvl(&self) -> u32{
+        let exp_sum: u64 = self.data.categories.iter().map(|category| category.exp_sum).sum();
+        self.app_config.exp_curve().stats(exp_sum).0
+    }
+
+    pub fn notify(&mut self, severity: Severity, message: String){
+        self.notifications.push(severity, message, true, self.app_config.notifications.history_limit);
+    }
+
+    pub fn handle_notifications(&mut self){
+        self.notifications.update(&self.app_config.notifications);
+    }
+
+    /// Announces events and runs their hooks, for events published since last call
+    pub fn handle_event_bus(&mut self){
+        for event in self.event_bus.take(){
+            if let Some(message) = event.announcement(){
+                let limit = self.app_config.notifications.history_limit;
+                match self.app_config.notifications.level_ups{
+                    AnnounceMode::Toast => self.notifications.push(Severity::Success, message, true, limit),
+                    AnnounceMode::History => self.notifications.push(Severity::Success, message, false, limit),
+                    AnnounceMode::Off => {},
+                }
+            }
+            let Some(commands) = self.app_config.hooks.get(event.name()).cloned() else {
+                continue;
+            };
+            for command in commands.iter(){
+                if let Err(error) = self.hooks.run(command, &event, &self.config_path){
+                    self.notify(Severity::Error, format!("Couldn't run {} hook `{}`: {}", event.name(), command, error));
                 }
             }
         }
+        self.hooks.reap();
     }
 
     pub fn render_widgets(&mut self, frame: &mut Frame){
         let layout_data: HashMap<String, Vec<Rect>> = to_layouts(&self.app_config.layouts, frame.area());
 
+        // Widgets render with shared app, so script output is computed before, without changing anything
+        let script_functions: Vec<String> = self.app_config.widgets.iter()
+            .filter(|widget| widget.visible)
+            .filter_map(|widget| match &widget.widget_type{
+                WidgetTypes::Script { function } => Some(function.to_string()),
+                _ => None,
+            })
+            .collect();
+        for function in script_functions{
+            let output = match self.run_widget_script(&function){
+                Ok(value) => value.to_string(),
+                Err(error) => format!("error: {}", error),
+            };
+            self.script_output.insert(function, output);
+        }
+
         for widget in self.app_config.widgets.iter(){
             if !widget.visible{
                 continue;
@@ -354,12 +1280,16 @@ impl App{
             }
         }
 
-        if self.result_message != String::new(){
-            render_result(self, frame, 60, 40, frame.area());
+        if self.command_line.open{
+            render_command_line(self, frame, frame.area());
         }
-        if self.error_message != String::new(){
-            render_error(self, frame, 60, 40, frame.area());
+        if self.notifications.history_open{
+            render_notification_history(self, frame, frame.area());
         }
+        if self.unclaimed_timer_time() > 0{
+            render_timer_claim(self, frame, frame.area());
+        }
+        render_toasts(self, frame, frame.area());
     }
 
     pub fn open_buffer(&mut self, name: String, mode: InputMode){
@@ -390,191 +1320,407 @@ impl App{
         }
     }
 
+    /// `name` is literal text or `$name` of buffer or additional data
     pub fn add_category(&mut self, name: String){
-        if name.starts_with("$"){
-            let name = name[1..].to_string();
-            if Some(name.clone()) == self.buffer_name{
-                for category in self.data.categories.iter(){
-                    if category.name == self.input_buffer{
-                        self.error_message = "Category already exist".to_string();
-                        return;
-                    }
-                }
-                let _ = self.data.add_category(Category::init(&self.input_buffer, self.app_config.base_exp));
-                self.result_message = "Category succesfully added".to_string();
-            }
-            else if let Some(Variant::Str(value)) = self.additional_data.get(&name){
-                for category in self.data.categories.iter(){
-                    if category.name == *value{
-                        self.error_message = "Category already exist".to_string();
-                        return;
-                    }
-                }
-                let _ = self.data.add_category(Category::init(value, self.app_config.base_exp));
-                self.result_message = "Category succesfully added".to_string();
-            }
-            else{
-                self.error_message = "Couldn't add category".to_string();
-            }
-        }
-        else{
-            self.error_message = "Category name needs to be a param, not literal".to_string();
+        let Some(name) = self.param_text(&name) else {
+            self.notify(Severity::Error, "Couldn't add category".to_string());
+            return;
+        };
+        if self.data.categories.iter().any(|category| category.name == name){
+            self.notify(Severity::Error, "Category already exist".to_string());
+            return;
         }
+        let _ = self.data.add_category(Category::init(&name, &self.app_config.exp_curve()));
+        self.notify(Severity::Success, "Category succesfully added".to_string());
     }
 
     pub fn add_task(&mut self, name: String, value: String){
-        if name.starts_with("$") && value.starts_with("$") && let Some(category_id) = self.additional_data.get("category_id"){
-            let name = name[1..].to_string();
-            let value = value[1..].to_string();
- 
-            if let Some(category_id) = variant_id_to_usize(category_id, self.data.categories.len()){
-                let task_name: Option<String> = if Some(name.clone()) == self.buffer_name{
-                    Some(self.input_buffer.to_string())
-                }else if let Some(Variant::Str(value)) = self.additional_data.get(&name){
-                    Some(value.to_string())
-                }else{
-                    self.error_message = "Cant get task name".to_string();
-                    None
-                };
-                
-                let task_exp: Option<u32> = if Some(value.clone()) == self.buffer_name{
-                    Some(self.input_buffer.parse::<u32>().expect(""))
-                }else if let Some(Variant::Int(value)) = self.additional_data.get(&value){
-                    Some(*value as u32)
-                }else{
-                    self.error_message = "Cant get task exp".to_string();
-                    None
-                };
+        let Some(category_id) = self.selected_category() else {
+            self.notify(Severity::Error, "cant get category".to_string());
+            return;
+        };
+        let Some(task_name) = self.param_text(&name) else {
+            self.notify(Severity::Error, "Cant get task name".to_string());
+            return;
+        };
+        let Some(task_exp) = self.param_number(&value) else {
+            self.notify(Severity::Error, "Cant get task exp".to_string());
+            return;
+        };
+        if self.data.categories[category_id].tasks.iter().any(|task| task.name == task_name){
+            self.notify(Severity::Error, "Task already exist".to_string());
+            return;
+        }
 
-                if let Some(category) = self.data.get_category_mut(category_id) && let Some(task_name) = task_name && let Some(task_exp) = task_exp{
-                    for task in category.tasks.iter(){
-                        if task.name == task_name{
-                            self.error_message = "Task already exist".to_string();
-                            return;
-                        }
-                    }
+        let _ = self.data.add_task(category_id, Task::init(task_name, task_exp));
+        self.notify(Severity::Success, "Task succesfully added".to_string());
+    }
 
-                    let _ = category.add_task(Task::init(task_name, task_exp));
-                    self.result_message = "Task succesfully added".to_string();
-                    return;
-                }
-                else{
-                    if self.error_message == String::new(){
-                        self.error_message = "cant get category".to_string();
-                    }
-                    return;
-                }
-            }
-            else{
-                self.error_message = "No category with id".to_string();
-            }
+    pub fn add_milestone(&mut self, name: String, value: String){
+        let Some(category_id) = self.selected_category() else {
+            self.notify(Severity::Error, "cant get category".to_string());
+            return;
+        };
+        let Some(milestone_name) = self.param_text(&name) else {
+            self.notify(Severity::Error, "Cant get milestone name".to_string());
+            return;
+        };
+        let Some(milestone_exp) = self.param_number(&value) else {
+            self.notify(Severity::Error, "Cant get milestone exp".to_string());
+            return;
+        };
+        if self.data.categories[category_id].milestones.iter().any(|milestone| milestone.name == milestone_name){
+            self.notify(Severity::Error, "Milestone already exist".to_string());
+            return;
         }
-        else{
-            self.error_message = "Fix configs".to_string();
+
+        let _ = self.data.add_milestone(category_id, Milestone::init(milestone_name, milestone_exp));
+        self.notify(Severity::Success, "Milestone succesfully added".to_string());
+    }
+
+    pub fn add_exp(&mut self, value: String){
+        let Some(exp) = self.param_number(&value) else {
+            self.notify(Severity::Error, "Cant get exp value".to_string());
+            return;
+        };
+
+        if let Some(category_id) = self.selected_category() && let Some(category) = self.data.get_category(category_id){
+            self.give_exp(category.get_uid(), exp, ExpSource::Manual);
+            return;
         }
+        self.notify(Severity::Error, "cant get category".to_string());
     }
 
-    pub fn add_milestone(&mut self, name: String, value: String){
-        if name.starts_with("$") && value.starts_with("$") && let Some(category_id) = self.additional_data.get("category_id"){
-            let name = name[1..].to_string();
-            let value = value[1..].to_string();
- 
-            if let Some(category_id) = variant_id_to_usize(category_id, self.data.categories.len()){
-                let milestone_name: Option<String> = if Some(name.clone()) == self.buffer_name{
-                    Some(self.input_buffer.to_string())
-                }else if let Some(Variant::Str(value)) = self.additional_data.get(&name){
-                    Some(value.to_string())
-                }else{
-                    self.error_message = "Cant get milestone name".to_string();
-                    None
-                };
-                
-                let milestone_exp: Option<u32> = if Some(value.clone()) == self.buffer_name{
-                    Some(self.input_buffer.parse::<u32>().expect(""))
-                }else if let Some(Variant::Int(value)) = self.additional_data.get(&value){
-                    Some(*value as u32)
-                }else{
-                    self.error_message = "Cant get milestone exp".to_string();
-                    None
-                };
+    pub fn set_recurrence(&mut self, value: String){
+        let Some(recurrence) = self.param_text(&value).and_then(|value| Recurrence::from_str(&value)) else {
+            self.notify(Severity::Error, "Cant parse recurrence, use daily, weekly <days>, every <n> or monthly <day>".to_string());
+            return;
+        };
 
-                if let Some(category) = self.data.get_category_mut(category_id) && let Some(milestone_name) = milestone_name && let Some(milestone_exp) = milestone_exp{
-                    for milestone in category.milestones.iter(){
-                        if milestone.name == milestone_name{
-                            self.error_message = "Milestone already exist".to_string();
-                            return;
-                        }
-                    }
+        if let Some((category_id, task_id)) = self.selected_task() && let Some(category) = self.data.get_category_mut(category_id) && let Some(task) = category.get_task_mut(task_id){
+            task.set_recurrence(recurrence);
+            let message = format!("Task now repeats {}", task.recurrence);
+            self.notify(Severity::Success, message);
+            return;
+        }
+        self.notify(Severity::Error, "cant get task".to_string());
+    }
 
-                    let _ = category.add_milestone(Milestone::init(milestone_name, milestone_exp));
-                    self.result_message = "Milestone succesfully added".to_string();
-                    return;
-                }
-                else{
-                    if self.error_message == String::new(){
-                        self.error_message = "cant get category".to_string();
-                    }
-                    return;
-                }
-            }
-            else{
-                self.error_message = "No category with id".to_string();
-            }
+    pub fn edit_category(&mut self, name: String){
+        let Some(name) = self.param_text(&name).filter(|name| !name.trim().is_empty()) else {
+            self.notify(Severity::Error, "Cant get category name".to_string());
+            return;
+        };
+        let Some(category_id) = self.selected_category() else {
+            self.notify(Severity::Error, "cant get category".to_string());
+            return;
+        };
+        if self.data.edit_category(category_id, name).is_err(){
+            self.notify(Severity::Error, "Category already exist".to_string());
         }
-        else{
-            self.error_message = "Fix configs".to_string();
+    }
+    pub fn remove_category(&mut self){
+        let Some(category_id) = self.selected_category() else {
+            self.notify(Severity::Error, "cant get category".to_string());
+            return;
+        };
+        // Its running timers are kept by undo, so they come back with the category
+        let _ = self.data.remove_category(category_id);
+    }
+    pub fn move_category(&mut self, by: i32){
+        if let Some(category_id) = self.selected_category() && self.data.move_category(category_id, by).is_ok(){
+            self.set_data("category_id".to_string(), Variant::Int(category_id as i64 + by as i64));
         }
     }
 
-    pub fn complete_task(&mut self){
-        if let Some(task_id) = self.additional_data.get("task_id") && let Some(category_id) = self.additional_data.get("category_id"){
-            if let Some(category_id) = variant_id_to_usize(category_id, self.data.categories.len()){ 
-                if let Some(category) = self.data.get_category_mut(category_id){
-                    if let Some(task_id) = variant_id_to_usize(task_id, category.tasks.len()){
-                        if let Some(task) = category.get_task(task_id){
-                            category.increase_exp(task.exp_reward, self.app_config.exp_power, self.app_config.base_exp);
-                        }
-                    }
-                }
+    pub fn edit_task(&mut self, name: String){
+        let Some(name) = self.param_text(&name).filter(|name| !name.trim().is_empty()) else {
+            self.notify(Severity::Error, "Cant get task name".to_string());
+            return;
+        };
+        let Some((category_id, task_id)) = self.selected_task() else {
+            self.notify(Severity::Error, "cant get task".to_string());
+            return;
+        };
+        if let Some(category) = self.data.get_category_mut(category_id) && category.edit_task(task_id, name).is_err(){
+            self.notify(Severity::Error, "Task already exist".to_string());
+        }
+    }
+    pub fn remove_task(&mut self){
+        if let Some((category_id, task_id)) = self.selected_task() && let Some(category) = self.data.get_category_mut(category_id){
+            let _ = category.remove_task(task_id);
+        }
+    }
+    pub fn move_task(&mut self, by: i32){
+        if let Some((category_id, task_id)) = self.selected_task() && let Some(category) = self.data.get_category_mut(category_id) && category.move_task(task_id, by).is_ok(){
+            self.set_data("task_id".to_string(), Variant::Int(task_id as i64 + by as i64));
+        }
+    }
+    pub fn set_task_exp(&mut self, value: String){
+        let Some(exp) = self.param_number(&value) else {
+            self.notify(Severity::Error, "Cant get task exp".to_string());
+            return;
+        };
+        if let Some((category_id, task_id)) = self.selected_task() && let Some(category) = self.data.get_category_mut(category_id) && let Some(task) = category.get_task_mut(task_id){
+            task.set_exp_revard(exp);
+        }
+    }
+
+    pub fn edit_milestone(&mut self, name: String){
+        let Some(name) = self.param_text(&name).filter(|name| !name.trim().is_empty()) else {
+            self.notify(Severity::Error, "Cant get milestone name".to_string());
+            return;
+        };
+        let Some((category_id, milestone_id)) = self.selected_milestone() else {
+            self.notify(Severity::Error, "cant get milestone".to_string());
+            return;
+        };
+        if let Some(category) = self.data.get_category_mut(category_id) && category.edit_milestone(milestone_id, name).is_err(){
+            self.notify(Severity::Error, "Milestone already exist".to_string());
+        }
+    }
+    pub fn remove_milestone(&mut self){
+        if let Some((category_id, milestone_id)) = self.selected_milestone() && let Some(category) = self.data.get_category_mut(category_id){
+            let _ = category.remove_milestone(milestone_id);
+        }
+    }
+    pub fn move_milestone(&mut self, by: i32){
+        if let Some((category_id, milestone_id)) = self.selected_milestone() && let Some(category) = self.data.get_category_mut(category_id) && category.move_milestone(milestone_id, by).is_ok(){
+            self.set_data("milestone_id".to_string(), Variant::Int(milestone_id as i64 + by as i64));
+        }
+    }
+    pub fn set_milestone_exp(&mut self, value: String){
+        let Some(exp) = self.param_number(&value) else {
+            self.notify(Severity::Error, "Cant get milestone exp".to_string());
+            return;
+        };
+        if let Some((category_id, milestone_id)) = self.selected_milestone() && let Some(category) = self.data.get_category_mut(category_id) && let Some(milestone) = category.get_milestone_mut(milestone_id){
+            milestone.set_exp_revard(exp);
+        }
+    }
+
+    /// Copies field of selected item to additional data, so edit buffers open with current value
+    pub fn prefill(&mut self, key: String, field: String){
+        let category = self.selected_category().and_then(|category_id| self.data.get_category(category_id));
+        let task = self.selected_task().and_then(|(category_id, task_id)| self.data.get_category(category_id)?.get_task(task_id));
+        let milestone = self.selected_milestone().and_then(|(category_id, milestone_id)| self.data.get_category(category_id)?.get_milestone(milestone_id));
+
+        let value = match field.trim(){
+            "category_name" => category.map(|category| Variant::Str(category.name.clone())),
+            "task_name" => task.map(|task| Variant::Str(task.name.clone())),
+            "task_exp" => task.map(|task| Variant::Int(task.exp_reward as i64)),
+            "task_recurrence" => task.map(|task| Variant::Str(task.recurrence.to_string())),
+            "milestone_name" => milestone.map(|milestone| Variant::Str(milestone.name.clone())),
+            "milestone_exp" => milestone.map(|milestone| Variant::Int(milestone.exp_reward as i64)),
+            _ => {
+                self.notify(Severity::Error, format!("Cant prefill {}", field));
+                return;
             }
+        };
+
+        match value{
+            Some(value) => self.set_data(key, value),
+            None => self.notify(Severity::Error, "Nothing selected".to_string()),
         }
     }
 
+    pub fn complete_task(&mut self){
+        if let Some((category_id, task_id)) = self.selected_task() && let Err(error) = self.complete_task_at(category_id, task_id){
+            self.notify(Severity::Error, error);
+        }
+    }
+
+    /// Completes task at position, giving its exp with streak bonus. Returns given exp
+    pub fn complete_task_at(&mut self, category_id: usize, task_id: usize) -> Result<u32, String>{
+        let Some(category) = self.data.get_category_mut(category_id) else {
+            return Err("No category with id".to_string());
+        };
+        let category_uid = category.get_uid();
+        let Some(task) = category.get_task_mut(task_id) else {
+            return Err("No task with id".to_string());
+        };
+        if task.complete().is_err(){
+            return Err("Task already done for this period".to_string());
+        }
+        let source = ExpSource::Task { uid: task.get_uid(), name: task.name.clone() };
+        // Task streaks count periods of its schedule, category streaks count days
+        let (exp_reward, task_streak) = (task.exp_reward, task.recurrence.is_recurring().then(|| task.recurrence.streak_days(task.streak)));
+
+        category.register_activity();
+        let streak_days = task_streak.unwrap_or(category.streak);
+        let exp_reward = self.app_config.streak_bonus.apply(exp_reward, streak_days);
+
+        self.give_exp(category_uid, exp_reward, source);
+        Ok(exp_reward)
+    }
+
     pub fn complete_milestone(&mut self){
-        if let Some(milestone_id) = self.additional_data.get("milestone_id") && let Some(category_id) = self.additional_data.get("category_id"){
-            if let Some(category_id) = variant_id_to_usize(category_id, self.data.categories.len()){ 
-                if let Some(category) = self.data.get_category_mut(category_id){
-                    if let Some(milestone_id) = variant_id_to_usize(milestone_id, category.tasks.len()){
-                        if let Some(milestone) = category.get_milestone(milestone_id){
-                            category.increase_exp(milestone.exp_reward, self.app_config.exp_power, self.app_config.base_exp);
-                            let _ = category.remove_milestone(milestone_id);
-                            self.set_data("milestone_id".to_string(), Variant::Int(0));
-                        }
-                    }
-                }
+        if let Some((category_id, milestone_id)) = self.selected_milestone() && let Some(category) = self.data.get_category(category_id) && let Some(milestone) = category.get_milestone(milestone_id){
+            let category_uid = category.get_uid();
+            let source = ExpSource::Milestone(milestone.name.clone());
+            let event = AppEvent::MilestoneCompleted { category: category.name.to_string(), category_uid, milestone: milestone.name.to_string(), exp: milestone.exp_reward };
+            let exp_reward = milestone.exp_reward;
+            self.event_bus.publish(event);
+            self.give_exp(category_uid, exp_reward, source);
+            if let Some(category) = self.data.get_category_uid_mut(category_uid){
+                let _ = category.remove_milestone(milestone_id);
             }
+            self.set_data("milestone_id".to_string(), Variant::Int(0));
         }
     }
 
-    pub fn toggle_timer(&mut self){
+    /// Starts timer for selected category, or pomodoro timer with `pomodoro`, stops one already running
+    pub fn toggle_timer(&mut self, pomodoro: bool){
         if let Some(category_id) = self.additional_data.get("category_id"){
             if let Some(category_id) = variant_id_to_usize(category_id, self.data.categories.len()){
                 if let Some(category) = self.data.get_category(category_id){
                     let category_uid = category.get_uid();
 
+                    let category_name = category.name.to_string();
+
                     for (i, timer) in self.timers.iter().enumerate(){
                         if timer.category_id == category_uid{
+                            let seconds = timer.active_seconds() as u64;
                             self.timers.remove(i);
+                            self.event_bus.publish(AppEvent::TimerStopped { category: category_name, category_uid, seconds });
                             return;
                         }
                     }
 
-                    self.timers.push(Timer::new(category, self.app_config.timer_frequency));
+                    let timer = if pomodoro{
+                        Timer::new_pomodoro(category, self.app_config.timer_frequency)
+                    }else{
+                        Timer::new(category, self.app_config.timer_frequency)
+                    };
+                    self.timers.push(timer);
+                    self.event_bus.publish(AppEvent::TimerStarted { category: category_name, category_uid });
                 }
             }
         }
     }
 
+    /// Pauses, or with `pause` false resumes, timer selected by `timer_id`
+    pub fn pause_timer(&mut self, pause: bool){
+        let Some(timer) = self.selected_timer().and_then(|timer_id| self.timers.get_mut(timer_id)) else {
+            self.notify(Severity::Error, "cant get timer".to_string());
+            return;
+        };
+        let changed = if pause { timer.pause() } else { timer.resume() };
+        if !changed{
+            let message = format!("Timer already {}", if pause { "paused" } else { "running" });
+            self.notify(Severity::Warning, message);
+        }
+    }
+
+    /// Literal command param, or value of `$name` taken from the open buffer or additional data
+    pub fn param_text(&self, param: &str) -> Option<String>{
+        let Some(name) = param.strip_prefix("$") else {
+            return Some(param.to_string());
+        };
+        if Some(name.to_string()) == self.buffer_name{
+            return Some(self.input_buffer.to_string());
+        }
+        match self.additional_data.get(name){
+            Some(Variant::Str(value)) => Some(value.to_string()),
+            Some(Variant::Int(value)) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+    pub fn param_number(&self, param: &str) -> Option<u32>{
+        self.param_text(param)?.trim().parse::<u32>().ok()
+    }
+
+    /// Current value of one of `condition::FACTS`
+    pub fn fact(&self, name: &str) -> String{
+        let category = self.selected_category().and_then(|category_id| self.data.get_category(category_id));
+        match name{
+            "state" => self.state.to_string(),
+            "buffer" => self.buffer_name.clone().unwrap_or_default(),
+            "category_count" => self.data.categories.len().to_string(),
+            "category_name" => category.map(|category| category.name.to_string()).unwrap_or_default(),
+            "category_lvl" => category.map(|category| category.lvl).unwrap_or(0).to_string(),
+            "category_streak" => category.map(|category| category.streak).unwrap_or(0).to_string(),
+            "task_count" => category.map(|category| category.tasks.len()).unwrap_or(0).to_string(),
+            "task_done" => {
+                let done = self.selected_task()
+                    .and_then(|(category_id, task_id)| self.data.categories[category_id].tasks.get(task_id))
+                    .is_some_and(|task| task.status() == TaskStatus::Done);
+                done.to_string()
+            }
+            "milestone_count" => category.map(|category| category.milestones.len()).unwrap_or(0).to_string(),
+            "timer_count" => self.timers.len().to_string(),
+            "timer_running" => self.timers.iter().any(|timer| !timer.is_paused()).to_string(),
+            "user_lvl" => self.user_lvl().to_string(),
+            _ => String::new(),
+        }
+    }
+
+    pub fn selected_category(&self) -> Option<usize>{
+        let category_id = self.additional_data.get("category_id")?;
+        let category_id = variant_id_to_usize(category_id, self.data.categories.len())?;
+        self.data.get_category(category_id).map(|_| category_id)
+    }
+    pub fn selected_timer(&self) -> Option<usize>{
+        let timer_id = self.additional_data.get("timer_id")?;
+        variant_id_to_usize(timer_id, self.timers.len())
+    }
+    pub fn selected_task(&self) -> Option<(usize, usize)>{
+        let category_id = self.selected_category()?;
+        let task_id = self.additional_data.get("task_id")?;
+        let task_id = variant_id_to_usize(task_id, self.data.get_category(category_id)?.tasks.len())?;
+        self.data.get_category(category_id)?.get_task(task_id).map(|_| (category_id, task_id))
+    }
+    pub fn selected_milestone(&self) -> Option<(usize, usize)>{
+        let category_id = self.selected_category()?;
+        let milestone_id = self.additional_data.get("milestone_id")?;
+        let milestone_id = variant_id_to_usize(milestone_id, self.data.get_category(category_id)?.milestones.len())?;
+        self.data.get_category(category_id)?.get_milestone(milestone_id).map(|_| (category_id, milestone_id))
+    }
+
+    /// Selects category, task or milestone by position or by `#unique_id`
+    pub fn select(&mut self, kind: String, reference: String){
+        let Some(reference) = self.param_text(&reference) else {
+            self.notify(Severity::Error, "Cant get selection".to_string());
+            return;
+        };
+        let key = match kind.trim().to_lowercase().as_str(){
+            "category" => "category_id",
+            "task" => "task_id",
+            "milestone" => "milestone_id",
+            _ => {
+                self.notify(Severity::Error, format!("Cant select {}", kind));
+                return;
+            }
+        };
+
+        if let Some(uid) = reference.trim().strip_prefix("#"){
+            let Ok(uid) = uid.parse::<u64>() else {
+                self.notify(Severity::Error, format!("Wrong id {}", reference));
+                return;
+            };
+
+            for (category_id, category) in self.data.categories.iter().enumerate(){
+                let position = match key{
+                    "task_id" => category.task_position(uid),
+                    "milestone_id" => category.milestone_position(uid),
+                    _ => (category.get_uid() == uid).then_some(category_id),
+                };
+                if let Some(position) = position{
+                    self.set_data("category_id".to_string(), Variant::Int(category_id as i64));
+                    self.set_data(key.to_string(), Variant::Int(position as i64));
+                    return;
+                }
+            }
+            self.notify(Severity::Error, format!("No {} with id {}", kind, uid));
+        }
+        else if let Ok(position) = reference.trim().parse::<i64>(){
+            self.set_data(key.to_string(), Variant::Int(position));
+        }
+        else{
+            self.notify(Severity::Error, format!("Wrong position {}", reference));
+        }
+    }
+
     pub fn set_data(&mut self, key: String, value: Variant){
         self.additional_data.insert(key, value);
     }
@@ -644,3 +1790,80 @@ impl App{
         }
     }
 }
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn app_with_category() -> App{
+        let mut app = App::new();
+        app.run_command(&AppCommands::AddCategory("A".to_string()));
+        app.additional_data.insert("category_id".to_string(), Variant::Int(0));
+        app
+    }
+
+    #[test]
+    fn commands_run_by_scripts_are_undone_with_the_script(){
+        let mut app = app_with_category();
+        let mut engine = ScriptEngine::new();
+        engine.load("test", "fn twice() { run(\"AddExp(10)\"); }").unwrap();
+        app.scripts = Rc::new(engine);
+
+        app.run_command(&AppCommands::Script("twice".to_string(), Vec::new()));
+        assert_eq!(app.data.categories[0].exp_sum, 10);
+        app.undo();
+        assert_eq!(app.data.categories[0].exp_sum, 0);
+        app.undo();
+        assert!(app.data.categories.is_empty());
+        app.redo();
+        app.redo();
+        assert_eq!(app.data.categories[0].exp_sum, 10);
+        assert_eq!(app.data.categories[0].lvl, app.app_config.exp_curve().stats(10).0);
+        assert_eq!(app.data.history.len(), 1);
+    }
+
+    #[test]
+    fn saving_keeps_data_changed_by_other_programs(){
+        let dir = std::env::temp_dir().join(format!("trexp_app_modified_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut app = app_with_category();
+        app.config_path = dir.clone();
+        app.write_data(false).unwrap();
+        app.write_data(false).unwrap();
+
+        let file = File::options().append(true).open(app.data_path()).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(5)).unwrap();
+        drop(file);
+        let content = fs::read_to_string(app.data_path()).unwrap();
+        assert!(app.write_data(false).is_err());
+        assert_eq!(fs::read_to_string(app.data_path()).unwrap(), content);
+
+        app.load_data().unwrap();
+        app.write_data(false).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn crash_snapshot_is_taken_only_after_changes(){
+        let mut app = app_with_category();
+        let snapshot: Mutex<Option<Data>> = Mutex::new(None);
+        app.update_crash_snapshot(&snapshot);
+        assert_eq!(snapshot.lock().unwrap().as_ref().map(|data| data.categories.len()), Some(1));
+
+        // Changes made without commands or exp aren't picked up until marked
+        app.data.categories.clear();
+        app.update_crash_snapshot(&snapshot);
+        assert_eq!(snapshot.lock().unwrap().as_ref().map(|data| data.categories.len()), Some(1));
+        app.mark_snapshot_stale();
+        app.update_crash_snapshot(&snapshot);
+        assert_eq!(snapshot.lock().unwrap().as_ref().map(|data| data.categories.len()), Some(0));
+
+        app.run_command(&AppCommands::AddCategory("B".to_string()));
+        let uid = app.data.categories[0].get_uid();
+        app.update_crash_snapshot(&snapshot);
+        app.give_exp(uid, 7, ExpSource::Manual);
+        app.update_crash_snapshot(&snapshot);
+        assert_eq!(snapshot.lock().unwrap().as_ref().map(|data| data.categories[0].exp_sum), Some(7));
+    }
+}
@@ -0,0 +1,213 @@
+use serde_json::{json, Value};
+
+use crate::{app::App, json_types::{Category, ExpSource, Task, TaskStatus}};
+
+pub const USAGE: &str = "Usage: trexp [command]
+
+Without command starts the app.
+
+Commands:
+  status                              levels, exp and streaks of categories
+  list [--json]                       categories with their tasks
+  add-task <category> <name> <exp>    adds task to category
+  complete <category> <task>          completes task, giving its exp
+  log <category> <minutes>            gives exp for time worked, like timers do
+  restore-backup [n]                  restores n-th newest backup of data.json
+  help                                shows this message
+
+Categories and tasks are picked by name, or by uid written as #uid";
+
+/// Commands run without starting the terminal interface
+pub enum CliCommand{
+    Status,
+    List { json: bool },
+    AddTask { category: String, name: String, exp: u32 },
+    Complete { category: String, task: String },
+    Log { category: String, minutes: u32 },
+    RestoreBackup(usize),
+    Help,
+}
+
+impl CliCommand{
+    /// Command from program arguments without program name, None starts the app
+    pub fn parse(args: &[String]) -> Result<Option<Self>, String>{
+        let Some(name) = args.first() else {
+            return Ok(None);
+        };
+        let params = &args[1..];
+        let command = match name.as_str(){
+            "status" => {
+                expect_params(name, params, 0)?;
+                CliCommand::Status
+            }
+            "list" => {
+                match params{
+                    [] => CliCommand::List { json: false },
+                    [flag] if flag == "--json" => CliCommand::List { json: true },
+                    _ => return Err(format!("`list` takes only optional --json, got `{}`", params.join(" "))),
+                }
+            }
+            "add-task" => {
+                expect_params(name, params, 3)?;
+                CliCommand::AddTask { category: params[0].to_string(), name: params[1].to_string(), exp: number(&params[2], "exp")? }
+            }
+            "complete" => {
+                expect_params(name, params, 2)?;
+                CliCommand::Complete { category: params[0].to_string(), task: params[1].to_string() }
+            }
+            "log" => {
+                expect_params(name, params, 2)?;
+                CliCommand::Log { category: params[0].to_string(), minutes: number(&params[1], "minutes")? }
+            }
+            "restore-backup" | "--restore-backup" => {
+                match params{
+                    [] => CliCommand::RestoreBackup(0),
+                    [index] => CliCommand::RestoreBackup(number(index, "backup index")? as usize),
+                    _ => return Err("`restore-backup` takes at most 1 argument".to_string()),
+                }
+            }
+            "help" | "--help" | "-h" => CliCommand::Help,
+            _ => return Err(format!("Unknown command `{}`", name)),
+        };
+        Ok(Some(command))
+    }
+
+    pub fn mutates_data(&self) -> bool{
+        matches!(self, CliCommand::AddTask { .. } | CliCommand::Complete { .. } | CliCommand::Log { .. })
+    }
+}
+
+fn expect_params(name: &str, params: &[String], count: usize) -> Result<(), String>{
+    if params.len() != count{
+        return Err(format!("`{}` takes {} argument(s), got {}", name, count, params.len()));
+    }
+    Ok(())
+}
+
+fn number(param: &str, what: &str) -> Result<u32, String>{
+    param.parse::<u32>().map_err(|_| format!("{} must be a positive number, got `{}`", what, param))
+}
+
+/// Position of category named `reference`, or with uid written as `#uid`
+fn find_category(app: &App, reference: &str) -> Result<usize, String>{
+    let position = match reference.strip_prefix("#").and_then(|uid| uid.parse::<u64>().ok()){
+        Some(uid) => app.data.categories.iter().position(|category| category.get_uid() == uid),
+        None => app.data.categories.iter().position(|category| category.name.eq_ignore_ascii_case(reference)),
+    };
+    position.ok_or_else(|| format!("No category `{}`", reference))
+}
+
+fn find_task(category: &Category, reference: &str) -> Result<usize, String>{
+    let position = match reference.strip_prefix("#").and_then(|uid| uid.parse::<u64>().ok()){
+        Some(uid) => category.task_position(uid),
+        None => category.tasks.iter().position(|task| task.name.eq_ignore_ascii_case(reference)),
+    };
+    position.ok_or_else(|| format!("No task `{}` in {}", reference, category.name))
+}
+
+fn status_name(status: TaskStatus) -> &'static str{
+    match status{
+        TaskStatus::Repeatable => "repeatable",
+        TaskStatus::Due => "due",
+        TaskStatus::Done => "done",
+    }
+}
+
+fn task_json(task: &Task) -> Value{
+    json!({
+        "uid": task.get_uid(),
+        "name": task.name,
+        "exp": task.exp_reward,
+        "recurrence": task.recurrence.to_string(),
+        "status": status_name(task.status()),
+        "streak": task.streak,
+        "best_streak": task.best_streak,
+    })
+}
+
+fn category_json(category: &Category) -> Value{
+    json!({
+        "uid": category.get_uid(),
+        "name": category.name,
+        "lvl": category.lvl,
+        "exp": category.exp,
+        "next_exp": category.next_exp,
+        "exp_sum": category.exp_sum,
+        "streak": category.streak,
+        "best_streak": category.best_streak,
+        "milestone_count": category.milestones.len(),
+        "tasks": category.tasks.iter().map(task_json).collect::<Vec<Value>>(),
+    })
+}
+
+/// Runs command on loaded app, saving data when command changed it. Returns text to print
+pub fn run(app: &mut App, command: CliCommand) -> Result<String, String>{
+    // Restoring backup is the way out of broken data, so only it runs without loaded data
+    if let Some(error) = &app.data_load_error && !matches!(command, CliCommand::RestoreBackup(_)){
+        return Err(format!("Couldn't load data.json: {}", error));
+    }
+    app.check_streaks();
+
+    let mutates_data = command.mutates_data();
+    let mut lines: Vec<String> = Vec::new();
+    match command{
+        CliCommand::Status => {
+            lines.push(format!("Level {}", app.fact("user_lvl")));
+            for category in app.data.categories.iter(){
+                let done = category.tasks.iter().filter(|task| task.status() == TaskStatus::Done).count();
+                lines.push(format!("{}: lvl {}, {}/{} exp, streak {} (best {}), {}/{} tasks done",
+                    category.name, category.lvl, category.exp, category.next_exp, category.streak, category.best_streak, done, category.tasks.len()));
+            }
+        }
+        CliCommand::List { json: true } => {
+            let categories: Vec<Value> = app.data.categories.iter().map(category_json).collect();
+            lines.push(serde_json::to_string_pretty(&categories).map_err(|error| error.to_string())?);
+        }
+        CliCommand::List { json: false } => {
+            for category in app.data.categories.iter(){
+                lines.push(format!("{} #{}", category.name, category.get_uid()));
+                for task in category.tasks.iter(){
+                    let mark = if task.status() == TaskStatus::Done { "x" } else { " " };
+                    lines.push(format!("  [{}] {} #{}: {} exp, {}", mark, task.name, task.get_uid(), task.exp_reward, task.recurrence));
+                }
+            }
+        }
+        CliCommand::AddTask { category, name, exp } => {
+            let category_id = find_category(app, &category)?;
+            if app.data.categories[category_id].tasks.iter().any(|task| task.name == name){
+                return Err(format!("Task `{}` already exists", name));
+            }
+            app.data.add_task(category_id, Task::init(name.to_string(), exp)).map_err(|_| "Couldn't add task".to_string())?;
+            lines.push(format!("Task `{}` added to {}", name, app.data.categories[category_id].name));
+        }
+        CliCommand::Complete { category, task } => {
+            let category_id = find_category(app, &category)?;
+            let task_id = find_task(&app.data.categories[category_id], &task)?;
+            let exp = app.complete_task_at(category_id, task_id)?;
+            let category = &app.data.categories[category_id];
+            lines.push(format!("Task `{}` completed, +{} exp ({} lvl {})", category.tasks[task_id].name, exp, category.name, category.lvl));
+        }
+        CliCommand::Log { category, minutes } => {
+            let category_id = find_category(app, &category)?;
+            // Timers give 1 exp every `timer_frequency` seconds
+            let exp = (minutes as f32 * 60.0 / app.app_config.timer_frequency) as u32;
+            let category = &app.data.categories[category_id];
+            let (uid, name, lvl) = (category.get_uid(), category.name.to_string(), category.lvl);
+            let lvl = app.give_exp(uid, exp, ExpSource::Timer { seconds: minutes as u64 * 60 }).map(|event| event.lvl_after).unwrap_or(lvl);
+            lines.push(format!("Logged {} min on {}, +{} exp (lvl {})", minutes, name, exp, lvl));
+        }
+        CliCommand::RestoreBackup(index) => {
+            app.restore_backup(index).map_err(|error| format!("Couldn't restore backup: {}", error))?;
+            lines.push(format!("Backup {} restored", index));
+        }
+        CliCommand::Help => lines.push(USAGE.to_string()),
+    }
+
+    // Commands run from cron or editors can come every few minutes, so like autosaves they don't rotate backups out
+    if mutates_data{
+        app.mark_snapshot_stale();
+        app.write_data(false).map_err(|error| format!("Couldn't save data: {}", error))?;
+    }
+    app.handle_event_bus();
+    Ok(lines.join("\n"))
+}
@@ -0,0 +1,144 @@
+/// Number of entered commands remembered by the prompt
+const HISTORY_LIMIT: usize = 100;
+
+/// State of the `:` command prompt
+pub struct CommandLine{
+    pub open: bool,
+    pub input: String,
+    pub error: Option<String>,
+    history: Vec<String>,
+    /// Position in `history` while browsing it, with the input typed before browsing
+    history_position: Option<(usize, String)>,
+    /// Candidates for the word being completed, with the one inserted last
+    completion: Option<(Vec<String>, usize)>,
+}
+
+impl CommandLine{
+    pub fn new() -> Self{
+        Self {
+            open: false,
+            input: String::new(),
+            error: None,
+            history: Vec::new(),
+            history_position: None,
+            completion: None,
+        }
+    }
+
+    pub fn open(&mut self){
+        self.open = true;
+        self.input = String::new();
+        self.error = None;
+        self.history_position = None;
+        self.completion = None;
+    }
+    pub fn close(&mut self){
+        self.open = false;
+        self.input = String::new();
+        self.error = None;
+        self.history_position = None;
+        self.completion = None;
+    }
+
+    pub fn push(&mut self, value: char){
+        self.input.push(value);
+        self.edited();
+    }
+    pub fn pop(&mut self){
+        self.input.pop();
+        self.edited();
+    }
+    fn edited(&mut self){
+        self.error = None;
+        self.history_position = None;
+        self.completion = None;
+    }
+
+    /// Remembers entered command, skipping repeats of the last one
+    pub fn add_history(&mut self, command: String){
+        if self.history.last() != Some(&command){
+            self.history.push(command);
+        }
+        if self.history.len() > HISTORY_LIMIT{
+            self.history.remove(0);
+        }
+    }
+    pub fn history_previous(&mut self){
+        let position = match &self.history_position{
+            Some((0, _)) => return,
+            Some((position, _)) => position - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.history_position = Some((self.history.len(), self.input.clone()));
+                self.history.len() - 1
+            }
+        };
+        self.show_history(position);
+    }
+    pub fn history_next(&mut self){
+        let Some((position, draft)) = &self.history_position else {
+            return;
+        };
+        if position + 1 >= self.history.len(){
+            self.input = draft.to_string();
+            self.history_position = None;
+        }else{
+            self.show_history(position + 1);
+        }
+        self.completion = None;
+    }
+    fn show_history(&mut self, position: usize){
+        if let Some((current, _)) = &mut self.history_position{
+            *current = position;
+        }
+        self.input = self.history[position].clone();
+        self.error = None;
+        self.completion = None;
+    }
+
+    /// Start of the word under the cursor, words are separated by command syntax
+    fn word_start(&self) -> usize{
+        self.input.rfind(|value: char| value.is_whitespace() || matches!(value, '(' | ')' | ',' | ';' | '"'))
+            .map(|position| position + 1)
+            .unwrap_or(0)
+    }
+
+    /// Completes the word under the cursor, pressing again cycles through candidates.
+    /// Words starting with `$` are completed from `variables`, others from `commands`
+    pub fn complete(&mut self, commands: &[&str], variables: Vec<&String>){
+        let start = self.word_start();
+
+        if let Some((candidates, id)) = &mut self.completion{
+            *id = (*id + 1) % candidates.len();
+            self.input.replace_range(start.., &candidates[*id]);
+            return;
+        }
+
+        let word = self.input[start..].to_string();
+        let mut candidates: Vec<String> = if let Some(name) = word.strip_prefix('$'){
+            variables.into_iter()
+                .filter(|variable| variable.starts_with(name))
+                .map(|variable| format!("${}", variable))
+                .collect()
+        }else{
+            commands.iter()
+                .filter(|command| command.to_lowercase().starts_with(&word.to_lowercase()))
+                .map(|command| command.to_string())
+                .collect()
+        };
+        candidates.sort();
+
+        if candidates.is_empty(){
+            return;
+        }
+        self.input.replace_range(start.., &candidates[0]);
+        if candidates.len() > 1{
+            self.completion = Some((candidates, 0));
+        }
+    }
+
+    /// Other candidates of the running completion, shown under the prompt
+    pub fn completions(&self) -> Option<&Vec<String>>{
+        self.completion.as_ref().map(|(candidates, _)| candidates)
+    }
+}
@@ -0,0 +1,344 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+enum TokenKind{
+    LParen,
+    RParen,
+    Comma,
+    Semicolon,
+    Str(String),
+    Word(String),
+}
+
+#[derive(Clone, Debug)]
+struct Token{
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError{
+    pub message: String,
+    pub position: usize,
+}
+
+impl fmt::Display for ParseError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl ParseError{
+    fn new(message: String, position: usize) -> Self{
+        Self { message, position }
+    }
+}
+
+/// Command argument
+#[derive(Clone, Debug, PartialEq)]
+pub enum Arg{
+    /// Quoted string, always taken as text
+    Str(String),
+    /// Unquoted text, like `CategoriesFocus`, `-1`, `data saved` or `$task_name`
+    Raw(String),
+    /// Nested command, like `Result(done)` in `If(cond, Result(done), Quit)`
+    Call(Call),
+}
+
+impl Arg{
+    pub fn text(&self) -> Result<String, String>{
+        match self{
+            Arg::Str(value) | Arg::Raw(value) => Ok(value.to_string()),
+            Arg::Call(call) => Err(format!("expected text, found command `{}`", call.name)),
+        }
+    }
+    pub fn parse<T: std::str::FromStr>(&self, type_name: &str) -> Result<T, String>{
+        let text = self.text()?;
+        text.trim().parse::<T>().map_err(|_| format!("expected {}, found `{}`", type_name, text))
+    }
+}
+
+impl fmt::Display for Arg{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            Arg::Str(value) => write!(f, "\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+            Arg::Raw(value) => write!(f, "{}", value),
+            Arg::Call(call) => write!(f, "{}", call),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call{
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub position: usize,
+}
+
+impl Call{
+    /// Lowercase name, commands are case insensitive
+    pub fn key(&self) -> String{
+        self.name.to_lowercase()
+    }
+    pub fn expect_args(&self, count: usize) -> Result<(), String>{
+        if self.args.len() != count{
+            return Err(format!("`{}` expects {} argument(s), got {}", self.name, count, self.args.len()));
+        }
+        Ok(())
+    }
+    pub fn arg(&self, id: usize) -> Result<&Arg, String>{
+        self.args.get(id).ok_or_else(|| format!("`{}` is missing argument {}", self.name, id + 1))
+    }
+}
+
+impl fmt::Display for Call{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        if self.args.is_empty(){
+            return write!(f, "{}", self.name);
+        }
+        let args: Vec<String> = self.args.iter().map(|arg| arg.to_string()).collect();
+        write!(f, "{}({})", self.name, args.join(", "))
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ParseError>{
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, value)) = chars.next(){
+        let kind = match value{
+            _ if value.is_whitespace() => continue,
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            ',' => TokenKind::Comma,
+            ';' => TokenKind::Semicolon,
+            '"' => {
+                let mut text = String::new();
+                let mut closed = false;
+                while let Some((position, value)) = chars.next(){
+                    match value{
+                        '"' => {
+                            closed = true;
+                            break;
+                        }
+                        '\\' => match chars.next(){
+                            Some((_, 'n')) => text.push('\n'),
+                            Some((_, 't')) => text.push('\t'),
+                            Some((_, value @ ('"' | '\\' | ',' | ';' | '(' | ')'))) => text.push(value),
+                            Some((_, value)) => return Err(ParseError::new(format!("unknown escape `\\{}`", value), position)),
+                            None => return Err(ParseError::new("unfinished escape".to_string(), position)),
+                        },
+                        _ => text.push(value),
+                    }
+                }
+                if !closed{
+                    return Err(ParseError::new("unclosed string".to_string(), start));
+                }
+                TokenKind::Str(text)
+            }
+            _ => {
+                let mut text = value.to_string();
+                while let Some((_, value)) = chars.peek(){
+                    if value.is_whitespace() || matches!(value, '(' | ')' | ',' | ';' | '"'){
+                        break;
+                    }
+                    text.push(*value);
+                    chars.next();
+                }
+                TokenKind::Word(text)
+            }
+        };
+        let end = chars.peek().map(|(position, _)| *position).unwrap_or(source.len());
+        tokens.push(Token { kind, start, end });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a>{
+    source: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl<'a> Parser<'a>{
+    fn peek(&self) -> Option<&TokenKind>{
+        self.tokens.get(self.position).map(|token| &token.kind)
+    }
+    fn peek_at(&self, offset: usize) -> Option<&TokenKind>{
+        self.tokens.get(self.position + offset).map(|token| &token.kind)
+    }
+    fn source_position(&self) -> usize{
+        self.tokens.get(self.position).map(|token| token.start).unwrap_or(self.source.len())
+    }
+    fn unexpected(&self, expected: &str) -> ParseError{
+        let found = match self.peek(){
+            Some(TokenKind::LParen) => "`(`".to_string(),
+            Some(TokenKind::RParen) => "`)`".to_string(),
+            Some(TokenKind::Comma) => "`,`".to_string(),
+            Some(TokenKind::Semicolon) => "`;`".to_string(),
+            Some(TokenKind::Str(value)) => format!("string \"{}\"", value),
+            Some(TokenKind::Word(value)) => format!("`{}`", value),
+            None => "end of command".to_string(),
+        };
+        ParseError::new(format!("expected {}, found {}", expected, found), self.source_position())
+    }
+
+    fn parse_commands(&mut self) -> Result<Vec<Call>, ParseError>{
+        let mut calls: Vec<Call> = Vec::new();
+        loop{
+            while self.peek() == Some(&TokenKind::Semicolon){
+                self.position += 1;
+            }
+            if self.peek().is_none(){
+                return Ok(calls);
+            }
+            calls.push(self.parse_call()?);
+            match self.peek(){
+                Some(TokenKind::Semicolon) | None => {},
+                _ => return Err(self.unexpected("`;`")),
+            }
+        }
+    }
+
+    fn parse_call(&mut self) -> Result<Call, ParseError>{
+        let position = self.source_position();
+        let name = match self.peek(){
+            Some(TokenKind::Word(name)) => name.to_string(),
+            _ => return Err(self.unexpected("command name")),
+        };
+        self.position += 1;
+
+        let mut args: Vec<Arg> = Vec::new();
+        if self.peek() == Some(&TokenKind::LParen){
+            self.position += 1;
+            if self.peek() == Some(&TokenKind::RParen){
+                self.position += 1;
+                return Ok(Call { name, args, position });
+            }
+            loop{
+                args.push(self.parse_arg()?);
+                match self.peek(){
+                    Some(TokenKind::Comma) => self.position += 1,
+                    Some(TokenKind::RParen) => {
+                        self.position += 1;
+                        break;
+                    }
+                    _ => return Err(self.unexpected("`,` or `)`")),
+                }
+            }
+        }
+        Ok(Call { name, args, position })
+    }
+
+    fn parse_arg(&mut self) -> Result<Arg, ParseError>{
+        match (self.peek(), self.peek_at(1)){
+            (Some(TokenKind::Str(value)), Some(TokenKind::Comma | TokenKind::RParen)) => {
+                let value = value.to_string();
+                self.position += 1;
+                return Ok(Arg::Str(value));
+            }
+            // `Name(` without whitespace starts a nested command, `done (x)` stays text
+            (Some(TokenKind::Word(_)), Some(TokenKind::LParen)) if self.tokens[self.position].end == self.tokens[self.position + 1].start => {
+                return Ok(Arg::Call(self.parse_call()?));
+            }
+            _ => {}
+        }
+
+        // Unquoted text runs until `,` or `)` outside of nested parentheses
+        let start = self.source_position();
+        let mut end = start;
+        let mut depth = 0;
+        loop{
+            match self.peek(){
+                Some(TokenKind::Comma | TokenKind::RParen) if depth == 0 => break,
+                Some(TokenKind::Semicolon) | None => return Err(self.unexpected("`)`")),
+                Some(TokenKind::LParen) => depth += 1,
+                Some(TokenKind::RParen) => depth -= 1,
+                _ => {}
+            }
+            end = self.tokens[self.position].end;
+            self.position += 1;
+        }
+        if start == end{
+            return Err(self.unexpected("argument"));
+        }
+        Ok(Arg::Raw(self.source[start..end].trim().to_string()))
+    }
+}
+
+/// Parses `;` separated commands, like `Set(last_state, TasksFocus);State(HelpPreview)`
+pub fn parse(source: &str) -> Result<Vec<Call>, ParseError>{
+    let mut parser = Parser {
+        source,
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+    parser.parse_commands()
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn raw(value: &str) -> Arg{
+        Arg::Raw(value.to_string())
+    }
+
+    #[test]
+    fn splits_commands_and_arguments(){
+        let calls = parse("Set(last_state, TasksFocus);;State(HelpPreview); Quit").unwrap();
+        let names: Vec<&str> = calls.iter().map(|call| call.name.as_str()).collect();
+        assert_eq!(names, ["Set", "State", "Quit"]);
+        assert_eq!(calls[0].args, [raw("last_state"), raw("TasksFocus")]);
+        assert!(calls[2].args.is_empty());
+        assert_eq!(calls[1].position, 29);
+    }
+
+    #[test]
+    fn quoted_strings_keep_separators_and_escapes(){
+        let calls = parse(r#"Notify("a, b; (c) \"d\"\n\\")"#).unwrap();
+        assert_eq!(calls[0].args, [Arg::Str("a, b; (c) \"d\"\n\\".to_string())]);
+
+        // Quote inside longer text stays part of raw argument
+        let calls = parse(r#"Notify(say "hi" now)"#).unwrap();
+        assert_eq!(calls[0].args, [raw(r#"say "hi" now"#)]);
+    }
+
+    #[test]
+    fn unquoted_text_and_nested_commands(){
+        let calls = parse("If(eq(category_count, 0), Result(no categories), Quit)").unwrap();
+        let Arg::Call(condition) = &calls[0].args[0] else { panic!("expected nested command") };
+        assert_eq!(condition.name, "eq");
+        assert_eq!(condition.args, [raw("category_count"), raw("0")]);
+        let Arg::Call(branch) = &calls[0].args[1] else { panic!("expected nested command") };
+        assert_eq!(branch.args, [raw("no categories")]);
+        assert_eq!(calls[0].args[2], raw("Quit"));
+
+        // Whitespace before `(` keeps it as text
+        let calls = parse("Result(done (x), -1)").unwrap();
+        assert_eq!(calls[0].args, [raw("done (x)"), raw("-1")]);
+    }
+
+    #[test]
+    fn display_parses_back_to_same_command(){
+        let calls = parse(r#"If(eq($name, "a,\"b\""), Notify(x y), Quit)"#).unwrap();
+        let reparsed = parse(&calls[0].to_string()).unwrap();
+        assert_eq!(reparsed[0].args, calls[0].args);
+    }
+
+    #[test]
+    fn reports_errors_with_position(){
+        let error = parse(r#"Notify("open)"#).unwrap_err();
+        assert_eq!(error, ParseError::new("unclosed string".to_string(), 7));
+        let error = parse(r#"Notify("\x")"#).unwrap_err();
+        assert_eq!(error.message, "unknown escape `\\x`");
+        let error = parse("State(a;b)").unwrap_err();
+        assert_eq!(error, ParseError::new("expected `)`, found `;`".to_string(), 7));
+        let error = parse("Set(a,)").unwrap_err();
+        assert_eq!(error.message, "expected argument, found `)`");
+        let error = parse("Quit Quit").unwrap_err();
+        assert_eq!(error.message, "expected `;`, found `Quit`");
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{app::App, command_parser::{Arg, Call}, wild_type::Variant};
+
+/// Values known by the app, usable as bare words in conditions like `eq(category_count, 0)`
+pub const FACTS: &[&str] = &[
+    "state", "buffer", "category_count", "category_name", "category_lvl", "category_streak",
+    "task_count", "task_done", "milestone_count", "timer_count", "timer_running", "user_lvl",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operand{
+    Literal(String),
+    /// `$name` of buffer or additional data
+    Variable(String),
+    Fact(String),
+}
+
+impl Operand{
+    fn from_arg(arg: &Arg) -> Result<Self, String>{
+        match arg{
+            Arg::Str(value) => Ok(Operand::Literal(value.to_string())),
+            Arg::Raw(value) => {
+                if let Some(name) = value.strip_prefix('$'){
+                    Ok(Operand::Variable(name.to_string()))
+                }else if FACTS.contains(&value.as_str()){
+                    Ok(Operand::Fact(value.to_string()))
+                }else{
+                    Ok(Operand::Literal(value.to_string()))
+                }
+            }
+            Arg::Call(call) => Err(format!("expected value, found `{}`", call)),
+        }
+    }
+
+    fn value(&self, app: &App) -> Result<String, String>{
+        match self{
+            Operand::Literal(value) => Ok(value.to_string()),
+            Operand::Variable(name) => app.param_text(&format!("${}", name))
+                .or_else(|| match app.additional_data.get(name){
+                    Some(Variant::Bool(value)) => Some(value.to_string()),
+                    Some(Variant::Float(value)) => Some(value.to_string()),
+                    _ => None,
+                })
+                .ok_or_else(|| format!("variable `{}` is not set", name)),
+            Operand::Fact(name) => Ok(app.fact(name)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison{
+    Eq,
+    Ne,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition{
+    Compare(Comparison, Operand, Operand),
+    /// True when variable is set
+    Exists(String),
+    Not(Box<Condition>),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    /// Bare value, false for `false`, `0` and empty text
+    Value(Operand),
+}
+
+impl Condition{
+    /// Parses conditions like `and(gt(category_count, 0), not(timer_running))`
+    pub fn from_arg(arg: &Arg) -> Result<Self, String>{
+        let Arg::Call(call) = arg else {
+            return Ok(Condition::Value(Operand::from_arg(arg)?));
+        };
+        Condition::from_call(call)
+    }
+
+    fn from_call(call: &Call) -> Result<Self, String>{
+        let comparison = match call.key().as_str(){
+            "eq" => Comparison::Eq,
+            "ne" => Comparison::Ne,
+            "gt" => Comparison::Gt,
+            "lt" => Comparison::Lt,
+            "ge" => Comparison::Ge,
+            "le" => Comparison::Le,
+            "not" => {
+                call.expect_args(1)?;
+                return Ok(Condition::Not(Box::new(Condition::from_arg(call.arg(0)?)?)));
+            }
+            "and" | "or" => {
+                if call.args.len() < 2{
+                    return Err(format!("`{}` expects at least 2 arguments, got {}", call.name, call.args.len()));
+                }
+                let conditions = call.args.iter().map(Condition::from_arg).collect::<Result<Vec<Condition>, String>>()?;
+                if call.key() == "and"{
+                    return Ok(Condition::And(conditions));
+                }
+                return Ok(Condition::Or(conditions));
+            }
+            "exists" => {
+                call.expect_args(1)?;
+                let name = call.arg(0)?.text()?;
+                return Ok(Condition::Exists(name.strip_prefix('$').unwrap_or(&name).to_string()));
+            }
+            _ => return Err(format!("unknown condition `{}`", call.name)),
+        };
+        call.expect_args(2)?;
+        Ok(Condition::Compare(comparison, Operand::from_arg(call.arg(0)?)?, Operand::from_arg(call.arg(1)?)?))
+    }
+
+    pub fn evaluate(&self, app: &App) -> Result<bool, String>{
+        match self{
+            Condition::Compare(comparison, left, right) => {
+                let left = left.value(app)?;
+                let right = right.value(app)?;
+                // Numbers compare by value, anything else as text
+                let ordering = match (left.trim().parse::<f64>(), right.trim().parse::<f64>()){
+                    (Ok(left), Ok(right)) => left.partial_cmp(&right),
+                    _ => Some(left.cmp(&right)),
+                };
+                let Some(ordering) = ordering else {
+                    return Ok(false);
+                };
+                Ok(match comparison{
+                    Comparison::Eq => ordering.is_eq(),
+                    Comparison::Ne => ordering.is_ne(),
+                    Comparison::Gt => ordering.is_gt(),
+                    Comparison::Lt => ordering.is_lt(),
+                    Comparison::Ge => ordering.is_ge(),
+                    Comparison::Le => ordering.is_le(),
+                })
+            }
+            Condition::Exists(name) => Ok(app.additional_data.contains_key(name) || app.buffer_name.as_ref() == Some(name)),
+            Condition::Not(condition) => Ok(!condition.evaluate(app)?),
+            Condition::And(conditions) => {
+                for condition in conditions.iter(){
+                    if !condition.evaluate(app)?{
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Condition::Or(conditions) => {
+                for condition in conditions.iter(){
+                    if condition.evaluate(app)?{
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Condition::Value(operand) => {
+                let value = operand.value(app)?;
+                let value = value.trim();
+                Ok(!(value.is_empty() || value == "false" || value.parse::<f64>().is_ok_and(|number| number == 0.0)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::command_parser;
+
+    fn condition(source: &str) -> Result<Condition, String>{
+        let calls = command_parser::parse(&format!("If({}, Bell)", source)).map_err(|error| error.to_string())?;
+        Condition::from_arg(&calls[0].args[0])
+    }
+
+    fn evaluate(app: &App, source: &str) -> Result<bool, String>{
+        condition(source)?.evaluate(app)
+    }
+
+    #[test]
+    fn parses_operands(){
+        let parsed = condition("eq($name, category_count)").unwrap();
+        assert_eq!(parsed, Condition::Compare(Comparison::Eq, Operand::Variable("name".to_string()), Operand::Fact("category_count".to_string())));
+        let parsed = condition("ne(\"state\", state)").unwrap();
+        assert_eq!(parsed, Condition::Compare(Comparison::Ne, Operand::Literal("state".to_string()), Operand::Fact("state".to_string())));
+        assert_eq!(condition("exists($x)").unwrap(), Condition::Exists("x".to_string()));
+    }
+
+    #[test]
+    fn compares_numbers_by_value_and_text_as_text(){
+        let app = App::new();
+        assert_eq!(evaluate(&app, "lt(9, 10)"), Ok(true));
+        assert_eq!(evaluate(&app, "lt(\"b\", \"a\")"), Ok(false));
+        assert_eq!(evaluate(&app, "eq(1.0, 1)"), Ok(true));
+        assert_eq!(evaluate(&app, "ge(category_count, 0)"), Ok(true));
+        assert_eq!(evaluate(&app, "gt(nan, nan)"), Ok(false));
+    }
+
+    #[test]
+    fn combines_conditions(){
+        let app = App::new();
+        assert_eq!(evaluate(&app, "and(eq(category_count, 0), not(timer_running))"), Ok(true));
+        assert_eq!(evaluate(&app, "or(gt(category_count, 0), timer_running)"), Ok(false));
+        assert_eq!(evaluate(&app, "not(0)"), Ok(true));
+        assert_eq!(evaluate(&app, "not(text)"), Ok(false));
+    }
+
+    #[test]
+    fn reads_variables(){
+        let mut app = App::new();
+        assert_eq!(evaluate(&app, "exists($count)"), Ok(false));
+        assert_eq!(evaluate(&app, "eq($count, 3)"), Err("variable `count` is not set".to_string()));
+        app.additional_data.insert("count".to_string(), Variant::Int(3));
+        assert_eq!(evaluate(&app, "exists($count)"), Ok(true));
+        assert_eq!(evaluate(&app, "eq($count, 3)"), Ok(true));
+    }
+
+    #[test]
+    fn rejects_invalid_conditions(){
+        assert_eq!(condition("maybe(1, 2)"), Err("unknown condition `maybe`".to_string()));
+        assert_eq!(condition("and(1)"), Err("`and` expects at least 2 arguments, got 1".to_string()));
+        assert_eq!(condition("eq(1)"), Err("`eq` expects 2 argument(s), got 1".to_string()));
+        assert_eq!(condition("eq(Quit(), 1)"), Err("expected value, found `Quit`".to_string()));
+    }
+}
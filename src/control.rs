@@ -0,0 +1,330 @@
+use std::{fs, io::{self, BufRead, BufReader, Read, Write}, os::unix::{fs::{DirBuilderExt, PermissionsExt}, net::{UnixListener, UnixStream}}, path::{Path, PathBuf}, time::Duration};
+
+use serde_json::{json, Value};
+
+use crate::{app::{App, AppCommands}, cli::{self, CliCommand}, macros, notifications::{Notification, Severity}};
+
+/// Requests longer than that close the connection
+const MAX_REQUEST_LEN: usize = 1 << 20;
+/// Clients not reading responses are dropped once that much is waiting
+const MAX_RESPONSE_BACKLOG: usize = 8 << 20;
+/// Time command line waits for running app to answer
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(10);
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const COMMAND_ERROR: i64 = -32000;
+
+struct Client{
+    stream: UnixStream,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    closed: bool,
+}
+
+/// Unix socket taking newline separated JSON-RPC 2.0 requests, polled from the app loop
+pub struct ControlSocket{
+    listener: UnixListener,
+    path: PathBuf,
+    clients: Vec<Client>,
+}
+
+impl ControlSocket{
+    /// Listens on `path`, replacing socket file left by app which didn't exit cleanly
+    pub fn bind(path: PathBuf) -> io::Result<Self>{
+        if path.exists(){
+            if UnixStream::connect(&path).is_ok(){
+                return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("another trexp is listening on {}", path.display())));
+            }
+            fs::remove_file(&path)?;
+        }
+        let listener = bind_private(&path)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self { listener, path, clients: Vec::new() })
+    }
+
+    /// Accepts connections and answers waiting requests without blocking
+    pub fn handle(&mut self, app: &mut App){
+        while let Ok((stream, _)) = self.listener.accept(){
+            if stream.set_nonblocking(true).is_ok(){
+                self.clients.push(Client { stream, input: Vec::new(), output: Vec::new(), closed: false });
+            }
+        }
+
+        for client in self.clients.iter_mut(){
+            client.receive();
+            while let Some(end) = client.input.iter().position(|byte| *byte == b'\n'){
+                let line: Vec<u8> = client.input.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                if line.trim().is_empty(){
+                    continue;
+                }
+                if let Some(response) = respond(app, &line){
+                    client.output.extend(response.to_string().as_bytes());
+                    client.output.push(b'\n');
+                }
+                if client.output.len() > MAX_RESPONSE_BACKLOG{
+                    client.closed = true;
+                    client.output.clear();
+                    client.input.clear();
+                }
+            }
+            if client.input.len() > MAX_REQUEST_LEN{
+                client.closed = true;
+                client.output.clear();
+            }
+            client.send();
+        }
+        self.clients.retain(|client| !client.closed || !client.output.is_empty());
+    }
+}
+
+/// Socket can run any command, so it's created in a directory only the owner can enter
+/// and moved to `path` once only the owner can connect to it
+fn bind_private(path: &Path) -> io::Result<UnixListener>{
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("trexp.sock");
+    let private_dir = path.with_file_name(format!(".{}.{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&private_dir);
+    fs::DirBuilder::new().mode(0o700).create(&private_dir)?;
+
+    let private_path = private_dir.join(name);
+    let result = UnixListener::bind(&private_path).and_then(|listener| {
+        fs::set_permissions(&private_path, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&private_path, path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_dir_all(&private_dir);
+    result
+}
+
+impl Drop for ControlSocket{
+    fn drop(&mut self){
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl Client{
+    fn receive(&mut self){
+        let mut buffer = [0u8; 4096];
+        // Waiting requests are handled before more is read, so input stays under the limit
+        while !self.closed && self.input.len() <= MAX_REQUEST_LEN{
+            match self.stream.read(&mut buffer){
+                Ok(0) => self.closed = true,
+                Ok(count) => self.input.extend_from_slice(&buffer[..count]),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {},
+                Err(_) => self.closed = true,
+            }
+        }
+    }
+
+    /// Writes as much of waiting responses as socket accepts, rest is sent on later polls
+    fn send(&mut self){
+        while !self.output.is_empty(){
+            match self.stream.write(&self.output){
+                Ok(0) => break,
+                Ok(count) => { self.output.drain(..count); }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {},
+                Err(_) => {
+                    self.closed = true;
+                    self.output.clear();
+                }
+            }
+        }
+    }
+}
+
+fn error(id: Value, code: i64, message: String) -> Value{
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Response to request line, None for notifications
+fn respond(app: &mut App, line: &str) -> Option<Value>{
+    let request: Value = match serde_json::from_str(line){
+        Ok(request) => request,
+        Err(parse_error) => return Some(error(Value::Null, PARSE_ERROR, parse_error.to_string())),
+    };
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return Some(error(id.unwrap_or(Value::Null), INVALID_REQUEST, "request has no method".to_string()));
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    let response = match call(app, method, params){
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id.clone().unwrap_or(Value::Null), "result": result }),
+        Err((code, message)) => error(id.clone().unwrap_or(Value::Null), code, message),
+    };
+    id.map(|_| response)
+}
+
+fn call(app: &mut App, method: &str, params: Value) -> Result<Value, (i64, String)>{
+    match method{
+        "data" => serde_json::to_value(&app.data).map_err(|error| (COMMAND_ERROR, error.to_string())),
+        "timers" => {
+            let timers: Vec<Value> = app.timers.iter().map(|timer| {
+                let pomodoro = timer.pomodoro_phase(&app.app_config.pomodoro).map(|(phase, work_number, left)| json!({
+                    "phase": phase.to_string(),
+                    "work_number": work_number,
+                    "seconds_left": left,
+                }));
+                json!({
+                    "category_uid": timer.category_id,
+                    "category_name": timer.category_name,
+                    "started_at": timer.started_at,
+                    "seconds": timer.get_second(),
+                    "unclaimed_seconds": timer.unclaimed,
+                    "paused": timer.is_paused(),
+                    "active_seconds": timer.active_seconds(),
+                    "pomodoro": pomodoro,
+                })
+            }).collect();
+            Ok(Value::Array(timers))
+        }
+        "state" => Ok(json!({
+            "state": app.state,
+            "input_mode": format!("{:?}", app.input_mode),
+            "buffer": app.input_buffer,
+        })),
+        "cli" => {
+            let args = match &params{
+                Value::Array(values) => values.iter().map(|value| value.as_str().map(str::to_string)).collect::<Option<Vec<String>>>(),
+                _ => None,
+            };
+            let args = args.ok_or_else(|| (INVALID_PARAMS, "expected array of command line arguments".to_string()))?;
+            let command = CliCommand::parse(&args)
+                .map_err(|error| (INVALID_PARAMS, error))?
+                .ok_or_else(|| (INVALID_PARAMS, "no command line command given".to_string()))?;
+            cli::run(app, command).map(|output| json!({ "output": output })).map_err(|error| (COMMAND_ERROR, error))
+        }
+        "command" => {
+            let source = match &params{
+                Value::String(source) => source.to_string(),
+                Value::Array(values) if values.len() == 1 && values[0].is_string() => values[0].as_str().unwrap_or_default().to_string(),
+                Value::Object(fields) if fields.get("command").is_some_and(Value::is_string) => fields["command"].as_str().unwrap_or_default().to_string(),
+                _ => return Err((INVALID_PARAMS, "expected command string".to_string())),
+            };
+            run(app, &source)
+        }
+        _ => {
+            let known = AppCommands::NAMES.iter().any(|name| name.eq_ignore_ascii_case(method)) || macros::find(&app.app_config.macros, method).is_some();
+            if !known{
+                return Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method)));
+            }
+            let args = match params{
+                Value::Null => Vec::new(),
+                Value::Array(values) => values,
+                _ => return Err((INVALID_PARAMS, "command params must be an array".to_string())),
+            };
+            let args: Vec<String> = args.iter().map(command_arg).collect::<Result<_, _>>()?;
+            run(app, &format!("{}({})", method, args.join(", ")))
+        }
+    }
+}
+
+/// JSON value written as command argument, strings are quoted so any text passes through
+fn command_arg(value: &Value) -> Result<String, (i64, String)>{
+    match value{
+        Value::String(text) => {
+            let mut quoted = String::from("\"");
+            for char in text.chars(){
+                match char{
+                    '"' | '\\' => { quoted.push('\\'); quoted.push(char); }
+                    '\n' => quoted.push_str("\\n"),
+                    '\t' => quoted.push_str("\\t"),
+                    _ => quoted.push(char),
+                }
+            }
+            quoted.push('"');
+            Ok(quoted)
+        }
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        _ => Err((INVALID_PARAMS, format!("unsupported command argument {}", value))),
+    }
+}
+
+/// Runs commands like the prompt does, reporting notifications they pushed
+fn run(app: &mut App, source: &str) -> Result<Value, (i64, String)>{
+    let commands = AppCommands::parse(source, &app.app_config.macros).map_err(|error| (INVALID_PARAMS, format!("Invalid command: {}", error)))?;
+
+    let first_id = app.notifications.next_id();
+    for command in commands.iter(){
+        app.run_command(command);
+    }
+    let mut notifications: Vec<&Notification> = app.notifications.history().take_while(|notification| notification.id >= first_id).collect();
+    notifications.reverse();
+
+    let errors: Vec<&str> = notifications.iter()
+        .filter(|notification| notification.severity == Severity::Error)
+        .map(|notification| notification.message.as_str())
+        .collect();
+    if !errors.is_empty(){
+        return Err((COMMAND_ERROR, errors.join("\n")));
+    }
+    let notifications: Vec<Value> = notifications.iter()
+        .map(|notification| json!({ "severity": notification.severity, "message": notification.message }))
+        .collect();
+    Ok(json!({ "notifications": notifications }))
+}
+
+/// Runs command line arguments in app listening on `path`, None when no app accepts connections there
+pub fn forward_cli(path: &Path, args: &[String]) -> Option<Result<String, String>>{
+    let stream = UnixStream::connect(path).ok()?;
+    Some(send_cli(stream, args).map_err(|error| format!("Couldn't run command in running app: {}", error)))
+}
+
+fn send_cli(mut stream: UnixStream, args: &[String]) -> Result<String, String>{
+    stream.set_read_timeout(Some(FORWARD_TIMEOUT)).map_err(|error| error.to_string())?;
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "cli", "params": args });
+    writeln!(stream, "{}", request).map_err(|error| error.to_string())?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).map_err(|error| error.to_string())?;
+    let response: Value = serde_json::from_str(&line).map_err(|error| format!("invalid response: {}", error))?;
+    if let Some(error) = response.get("error"){
+        return Err(error["message"].as_str().unwrap_or("unknown error").to_string());
+    }
+    Ok(response["result"]["output"].as_str().unwrap_or_default().to_string())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf{
+        let dir = std::env::temp_dir().join(format!("trexp_control_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn runs_forwarded_command_line_in_app(){
+        let dir = temp_dir("forward");
+        let mut app = App::new();
+        app.config_path = dir.clone();
+        app.run_command(&AppCommands::AddCategory("Work".to_string()));
+        let mut socket = ControlSocket::bind(dir.join("trexp.sock")).unwrap();
+        assert_eq!(fs::metadata(dir.join("trexp.sock")).unwrap().permissions().mode() & 0o777, 0o600);
+
+        let path = dir.join("trexp.sock");
+        let client = std::thread::spawn(move || {
+            let args: Vec<String> = ["add-task", "Work", "Read", "5"].iter().map(|arg| arg.to_string()).collect();
+            forward_cli(&path, &args)
+        });
+        while !client.is_finished(){
+            socket.handle(&mut app);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(client.join().unwrap(), Some(Ok("Task `Read` added to Work".to_string())));
+        assert_eq!(app.data.categories[0].tasks[0].name, "Read");
+        assert!(fs::read_to_string(dir.join("data.json")).unwrap().contains("\"Read\""));
+
+        drop(socket);
+        assert_eq!(forward_cli(&dir.join("trexp.sock"), &["status".to_string()]), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
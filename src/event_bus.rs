@@ -0,0 +1,100 @@
+/// Things that happened in the app, published by `App` and handled once per loop
+#[derive(Clone, Debug)]
+pub enum AppEvent{
+    CategoryLevelUp { category: String, category_uid: u64, lvl_before: u32, lvl_after: u32 },
+    UserLevelUp { lvl_before: u32, lvl_after: u32 },
+    MilestoneCompleted { category: String, category_uid: u64, milestone: String, exp: u32 },
+    TimerStarted { category: String, category_uid: u64 },
+    TimerStopped { category: String, category_uid: u64, seconds: u64 },
+    PomodoroPhase { category: String, category_uid: u64, phase: String },
+}
+
+impl AppEvent{
+    /// Names used as keys of `hooks` in config
+    pub const NAMES: &'static [&'static str] = &[
+        "category_level_up",
+        "user_level_up",
+        "milestone_completed",
+        "timer_started",
+        "timer_stopped",
+        "pomodoro_phase",
+    ];
+
+    pub fn name(&self) -> &'static str{
+        match self{
+            AppEvent::CategoryLevelUp { .. } => "category_level_up",
+            AppEvent::UserLevelUp { .. } => "user_level_up",
+            AppEvent::MilestoneCompleted { .. } => "milestone_completed",
+            AppEvent::TimerStarted { .. } => "timer_started",
+            AppEvent::TimerStopped { .. } => "timer_stopped",
+            AppEvent::PomodoroPhase { .. } => "pomodoro_phase",
+        }
+    }
+
+    /// Message shown for level-ups, other events aren't announced
+    pub fn announcement(&self) -> Option<String>{
+        match self{
+            AppEvent::CategoryLevelUp { category, lvl_after, .. } => Some(format!("{} reached level {}", category, lvl_after)),
+            AppEvent::UserLevelUp { lvl_after, .. } => Some(format!("You reached level {}", lvl_after)),
+            _ => None,
+        }
+    }
+
+    /// Event details as `TREXP_` environment variables
+    pub fn variables(&self) -> Vec<(String, String)>{
+        let mut variables: Vec<(&str, String)> = vec![("EVENT", self.name().to_string())];
+        match self{
+            AppEvent::CategoryLevelUp { category, category_uid, lvl_before, lvl_after } => {
+                variables.push(("CATEGORY", category.to_string()));
+                variables.push(("CATEGORY_UID", category_uid.to_string()));
+                variables.push(("LVL_BEFORE", lvl_before.to_string()));
+                variables.push(("LVL", lvl_after.to_string()));
+            }
+            AppEvent::UserLevelUp { lvl_before, lvl_after } => {
+                variables.push(("LVL_BEFORE", lvl_before.to_string()));
+                variables.push(("LVL", lvl_after.to_string()));
+            }
+            AppEvent::MilestoneCompleted { category, category_uid, milestone, exp } => {
+                variables.push(("CATEGORY", category.to_string()));
+                variables.push(("CATEGORY_UID", category_uid.to_string()));
+                variables.push(("MILESTONE", milestone.to_string()));
+                variables.push(("EXP", exp.to_string()));
+            }
+            AppEvent::TimerStarted { category, category_uid } => {
+                variables.push(("CATEGORY", category.to_string()));
+                variables.push(("CATEGORY_UID", category_uid.to_string()));
+            }
+            AppEvent::TimerStopped { category, category_uid, seconds } => {
+                variables.push(("CATEGORY", category.to_string()));
+                variables.push(("CATEGORY_UID", category_uid.to_string()));
+                variables.push(("SECONDS", seconds.to_string()));
+            }
+            AppEvent::PomodoroPhase { category, category_uid, phase } => {
+                variables.push(("CATEGORY", category.to_string()));
+                variables.push(("CATEGORY_UID", category_uid.to_string()));
+                variables.push(("PHASE", phase.to_string()));
+            }
+        }
+        variables.into_iter().map(|(key, value)| (format!("TREXP_{}", key), value)).collect()
+    }
+}
+
+/// Queue of events waiting to be handled
+pub struct EventBus{
+    pending: Vec<AppEvent>,
+}
+
+impl EventBus{
+    pub fn new() -> Self{
+        Self { pending: Vec::new() }
+    }
+
+    pub fn publish(&mut self, event: AppEvent){
+        self.pending.push(event);
+    }
+
+    /// Events published since last call, oldest first
+    pub fn take(&mut self) -> Vec<AppEvent>{
+        std::mem::take(&mut self.pending)
+    }
+}
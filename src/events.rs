@@ -17,15 +17,39 @@ pub fn handle_events(app: &mut App, timeout: Duration) -> io::Result<()>{
 }
 
 fn handle_key_event(app: &mut App, key_event: KeyEvent){
-    if app.error_message != String::new() || app.result_message != String::new() {
-        match key_event.code {
-            KeyCode::Char('q') | KeyCode::Enter | KeyCode::Esc => {
-                app.error_message = String::new();
-                app.result_message = String::new();
-            },
+    if app.unclaimed_timer_time() > 0{
+        match key_event.code{
+            KeyCode::Char('y') | KeyCode::Enter => app.claim_timer_time(true),
+            KeyCode::Char('n') | KeyCode::Esc => app.claim_timer_time(false),
+            _ => {}
+        }
+    }
+    else if app.notifications.history_open{
+        match key_event.code{
+            KeyCode::Char('q') | KeyCode::Enter | KeyCode::Esc => app.notifications.toggle_history(),
+            KeyCode::Up => app.notifications.scroll_history(-1),
+            KeyCode::Down => app.notifications.scroll_history(1),
             _ => {}
         }
     }
+    else if app.command_line.open{
+        match key_event.code{
+            KeyCode::Char(value) => app.command_line.push(value),
+            KeyCode::Backspace => app.command_line.pop(),
+            KeyCode::Up => app.command_line.history_previous(),
+            KeyCode::Down => app.command_line.history_next(),
+            KeyCode::Tab => app.complete_command_line(),
+            KeyCode::Enter => app.submit_command_line(),
+            KeyCode::Esc => app.command_line.close(),
+            _ => {}
+        }
+    }
+    else if app.input_mode == InputMode::Undefined && is_key(key_event, &app.app_config.command_key){
+        app.command_line.open();
+    }
+    else if app.input_mode == InputMode::Undefined && is_key(key_event, &app.app_config.notifications.history_key){
+        app.notifications.toggle_history();
+    }
     else if app.input_mode != InputMode::Undefined && let Some(event_config) = app.app_config.keybinds.get(&app.state){
         match key_event.code{
             KeyCode::Char(value) => {
@@ -61,6 +85,15 @@ fn handle_key_event(app: &mut App, key_event: KeyEvent){
     }
 }
 
+fn is_key(key_event: KeyEvent, key: &str) -> bool{
+    if key.is_empty(){
+        return false;
+    }
+    let key_str = key_event_to_string(key_event);
+    // Terminals don't agree on reporting Shift for symbols like `:`
+    key_str == key || key_str.strip_prefix("Shift+") == Some(key)
+}
+
 fn key_event_to_string(event: KeyEvent) -> String {
     let mut parts = vec![];
 
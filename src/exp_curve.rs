@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+/// Formula for exp required to advance from one level to the next
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ExpCurve{
+    /// base * lvl^power
+    Power {base: u32, power: f32},
+    /// base + step * (lvl - 1)
+    Linear {base: u32, step: u32},
+    /// base * factor^(lvl - 1)
+    Exponential {base: u32, factor: f32},
+    /// Explicit requirements per level, last value repeats for higher levels
+    Table(Vec<u32>),
+}
+
+impl ExpCurve{
+    /// Exp required to advance from `lvl` to `lvl + 1`, never less than 1
+    pub fn required(&self, lvl: u32) -> u32{
+        let lvl = lvl.max(1);
+        let required = match self{
+            ExpCurve::Power { base, power } => (*base as f32 * (lvl as f32).powf(*power)) as u32,
+            ExpCurve::Linear { base, step } => base.saturating_add(step.saturating_mul(lvl - 1)),
+            ExpCurve::Exponential { base, factor } => (*base as f32 * factor.powi(lvl as i32 - 1)) as u32,
+            ExpCurve::Table(values) => {
+                values.get(lvl as usize - 1).or(values.last()).copied().unwrap_or(1)
+            }
+        };
+        required.max(1)
+    }
+
+    /// Identifies curve settings, used to detect changes between runs
+    pub fn fingerprint(&self) -> String{
+        serde_json::to_string(self).expect("couldnt serialize exp curve")
+    }
+
+    /// Level, exp into the level and exp required for the next one after collecting `exp_sum` exp
+    pub fn stats(&self, exp_sum: u64) -> (u32, u64, u64){
+        self.advance(1, exp_sum)
+    }
+
+    /// Levels gained with `exp` collected at `lvl`, returned like `stats`. Levels requiring
+    /// the same exp are passed together, so curves stuck at low requirements don't take a step per level
+    pub fn advance(&self, mut lvl: u32, mut exp: u64) -> (u32, u64, u64){
+        loop{
+            let required = self.required(lvl) as u64;
+            let affordable = (exp / required).min((u32::MAX - lvl) as u64);
+            if affordable == 0{
+                return (lvl, exp, required);
+            }
+
+            // Equal requirements of a monotonic curve are next to each other, so the run of them
+            // is found by checking its last level while doubling or halving its length
+            let mut run: u64 = 1;
+            let mut step: u64 = 1;
+            while self.monotonic_from(lvl) && run < affordable{
+                let candidate = (run + step).min(affordable);
+                if self.required(lvl + candidate as u32 - 1) as u64 == required{
+                    run = candidate;
+                    step *= 2;
+                }else if step > 1{
+                    step /= 2;
+                }else{
+                    break;
+                }
+            }
+            lvl += run as u32;
+            exp -= run * required;
+        }
+    }
+
+    /// True when requirements never go back once changed from `lvl` on
+    fn monotonic_from(&self, lvl: u32) -> bool{
+        match self{
+            ExpCurve::Table(values) => lvl as usize >= values.len(),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::json_types::Category;
+
+    /// Level-up done one level at a time, as `stats` did before runs were skipped
+    fn stats_by_level(curve: &ExpCurve, exp_sum: u64) -> (u32, u64, u64){
+        let mut lvl: u32 = 1;
+        let mut cur_exp = exp_sum;
+        while cur_exp >= curve.required(lvl) as u64{
+            cur_exp -= curve.required(lvl) as u64;
+            lvl += 1;
+        }
+        (lvl, cur_exp, curve.required(lvl) as u64)
+    }
+
+    fn curves() -> Vec<ExpCurve>{
+        vec![
+            ExpCurve::Power { base: 100, power: 1.5 },
+            ExpCurve::Power { base: 1, power: 0.1 },
+            ExpCurve::Power { base: 50, power: -1.0 },
+            ExpCurve::Linear { base: 10, step: 5 },
+            ExpCurve::Linear { base: 0, step: 0 },
+            ExpCurve::Exponential { base: 20, factor: 1.2 },
+            ExpCurve::Exponential { base: 40, factor: 0.5 },
+            ExpCurve::Table(vec![3, 1, 4, 1, 5, 9, 2, 6]),
+            ExpCurve::Table(Vec::new()),
+        ]
+    }
+
+    #[test]
+    fn stats_match_level_by_level(){
+        for curve in curves(){
+            for exp_sum in [0, 1, 2, 7, 99, 100, 101, 1_000, 12_345, 250_000]{
+                assert_eq!(curve.stats(exp_sum), stats_by_level(&curve, exp_sum), "{:?} with {} exp", curve, exp_sum);
+            }
+        }
+    }
+
+    #[test]
+    fn stats_match_category_level_ups(){
+        for curve in curves(){
+            let mut category = Category::init("A", &curve);
+            for exp in [1, 0, 5, 37, 120, 3, 999, 4_000, 20_000]{
+                category.increase_exp(exp, &curve);
+                let (lvl, cur_exp, next_exp) = curve.stats(category.exp_sum);
+                assert_eq!((category.lvl, category.exp as u64, category.next_exp as u64), (lvl, cur_exp, next_exp), "{:?}", curve);
+            }
+        }
+    }
+
+    #[test]
+    fn levels_requiring_one_exp_are_counted_at_once(){
+        let curve = ExpCurve::Table(vec![1]);
+        assert_eq!(curve.stats(5_000_000_000), (u32::MAX, 5_000_000_000 - (u32::MAX as u64 - 1), 1));
+        let curve = ExpCurve::Power { base: 1, power: 0.01 };
+        assert_eq!(curve.stats(3_000_000_000).1, 0);
+    }
+}
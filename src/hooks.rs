@@ -0,0 +1,42 @@
+use std::{io, path::Path, process::{Child, Command, Stdio}};
+
+use crate::event_bus::AppEvent;
+
+/// Runs shell commands of config hooks without waiting for them
+pub struct HookRunner{
+    running: Vec<Child>,
+}
+
+impl HookRunner{
+    pub fn new() -> Self{
+        Self { running: Vec::new() }
+    }
+
+    /// Starts `command` in `directory` with event details in environment
+    pub fn run(&mut self, command: &str, event: &AppEvent, directory: &Path) -> io::Result<()>{
+        let mut process = if cfg!(windows){
+            let mut process = Command::new("cmd");
+            process.arg("/C").arg(command);
+            process
+        }else{
+            let mut process = Command::new("sh");
+            process.arg("-c").arg(command);
+            process
+        };
+        // Output would draw over the terminal interface
+        process.envs(event.variables())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if directory.is_dir(){
+            process.current_dir(directory);
+        }
+        self.running.push(process.spawn()?);
+        Ok(())
+    }
+
+    /// Collects exit status of finished commands
+    pub fn reap(&mut self){
+        self.running.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+    }
+}
@@ -1,34 +1,118 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fmt, time::{SystemTime, UNIX_EPOCH}};
+
+use chrono::NaiveDate;
 
 use serde::{Deserialize, Serialize};
 
-pub fn calculate_exp(lvl: u32, power: f32, base: u32) -> u32{
-    (base as f32 * (lvl as f32).powf(power)) as u32
+use crate::{exp_curve::ExpCurve, migrations::DATA_VERSION, schedule::{local_date, today, Recurrence}};
+
+pub fn timestamp() -> u64{
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH).expect("")
+        .as_secs()
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Data{
+    pub version: u32,
     pub categories: Vec<Category>,
+    pub history: Vec<ExpEvent>,
+    /// Fingerprint of the exp curve levels were calculated with
+    pub curve_fingerprint: String,
+    /// Next free unique id, shared by categories, tasks and milestones
+    next_uid: u64,
+    /// Timers running when data was saved, restored on launch
+    pub timers: Vec<TimerRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TimerRecord{
+    pub category_uid: u64,
+    /// Unix seconds timer was started at
+    pub started_at: u64,
+    /// Unix seconds up to which timer exp was given
+    pub credited_until: u64,
+    /// Seconds of interval done, for timers saved while paused
+    #[serde(default)]
+    pub paused: Option<f32>,
+    #[serde(default)]
+    pub pomodoro: Option<PomodoroRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PomodoroRecord{
+    /// Active seconds up to `credited_until` of its timer
+    pub active_seconds: f32,
+    pub rewarded_exp: u32,
+    pub rewarded_cycles: u32,
 }
 
 impl Data{
     pub fn new() -> Self{
         Self {
-            categories: Vec::new()
+            version: DATA_VERSION,
+            categories: Vec::new(),
+            history: Vec::new(),
+            curve_fingerprint: String::new(),
+            next_uid: 1,
+            timers: Vec::new(),
         }
     }
-    pub fn add_category(&mut self, category: Category) -> Result<(), ()>{
+    pub fn generate_uid(&mut self) -> u64{
+        let uid = self.next_uid.max(1);
+        self.next_uid = uid + 1;
+        uid
+    }
+    /// Rebuilds level state of every category from its exp sum
+    pub fn recalculate(&mut self, curve: &ExpCurve){
+        for category in self.categories.iter_mut(){
+            category.recalculate(curve);
+        }
+        self.curve_fingerprint = curve.fingerprint();
+    }
+    /// Adds exp to category with provided uid and stores the change in history
+    pub fn increase_exp(&mut self, uid: u64, exp: u32, source: ExpSource, curve: &ExpCurve) -> Option<&ExpEvent>{
+        let category = self.get_category_uid_mut(uid)?;
+
+        let lvl_before = category.lvl;
+        category.increase_exp(exp, curve);
+        let lvl_after = category.lvl;
+
+        self.history.push(ExpEvent {
+            timestamp: timestamp(),
+            category_uid: uid,
+            source,
+            amount: exp,
+            lvl_before,
+            lvl_after,
+        });
+        self.history.last()
+    }
+    /// History entries of category with provided uid, oldest first
+    pub fn category_history(&self, uid: u64) -> impl DoubleEndedIterator<Item = &ExpEvent>{
+        self.history.iter().filter(move |event| event.category_uid == uid)
+    }
+    pub fn add_category(&mut self, mut category: Category) -> Result<(), ()>{
         for old_category in self.categories.iter(){
             if old_category.name == category.name{
                 return Err(());
             }
         }
+        category.unique_id = self.generate_uid();
         self.categories.push(category);
         Ok(())
     }
+    pub fn add_task(&mut self, category_id: usize, mut task: Task) -> Result<(), ()>{
+        task.unique_id = self.generate_uid();
+        self.get_category_mut(category_id).ok_or(())?.add_task(task)
+    }
+    pub fn add_milestone(&mut self, category_id: usize, mut milestone: Milestone) -> Result<(), ()>{
+        milestone.unique_id = self.generate_uid();
+        self.get_category_mut(category_id).ok_or(())?.add_milestone(milestone)
+    }
     pub fn edit_category(&mut self, id: usize, name: String) -> Result<(), ()>{
-        for old_category in self.categories.iter(){
-            if old_category.name == name{
+        for (old_id, old_category) in self.categories.iter().enumerate(){
+            if old_category.name == name && old_id != id{
                 return Err(());
             }
         }
@@ -76,7 +160,40 @@ impl Data{
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ExpSource{
+    /// Task uid and its name at completion
+    Task { uid: u64, name: String },
+    Milestone(String),
+    /// Seconds of tracked time exp was given for
+    Timer { seconds: u64 },
+    PomodoroBonus,
+    Manual,
+}
+
+impl fmt::Display for ExpSource{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            ExpSource::Task { name, .. } => write!(f, "task {}", name),
+            ExpSource::Milestone(name) => write!(f, "milestone {}", name),
+            ExpSource::Timer { .. } => write!(f, "timer"),
+            ExpSource::PomodoroBonus => write!(f, "pomodoro bonus"),
+            ExpSource::Manual => write!(f, "manual"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ExpEvent{
+    pub timestamp: u64,
+    pub category_uid: u64,
+    pub source: ExpSource,
+    pub amount: u32,
+    pub lvl_before: u32,
+    pub lvl_after: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Category{
     unique_id: u64,
     pub name: String,
@@ -86,17 +203,19 @@ pub struct Category{
     pub lvl: u32,
     pub tasks: Vec<Task>,
     pub milestones: Vec<Milestone>,
+
+    pub streak: u32,
+    pub best_streak: u32,
+    pub last_active: Option<u64>,
+
+    /// Overrides exp curve from app config
+    pub exp_curve: Option<ExpCurve>,
 }
 
 impl Category{
     pub fn new() -> Self{
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH).expect("");
-
-        let timestamp = now.as_secs();
-
         Self {
-            unique_id: timestamp,
+            unique_id: 0,
             name: String::new(),
             exp_sum: 0,
             exp: 0,
@@ -104,23 +223,26 @@ impl Category{
             lvl: 1,
             tasks: Vec::new(),
             milestones: Vec::new(),
+            streak: 0,
+            best_streak: 0,
+            last_active: None,
+            exp_curve: None,
         }
     }
-    pub fn init(name: &str, base: u32) -> Self{
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH).expect("");
-
-        let timestamp = now.as_secs();
-
+    pub fn init(name: &str, curve: &ExpCurve) -> Self{
         Self {
-            unique_id: timestamp,
+            unique_id: 0,
             name: name.to_string(),
             exp_sum: 0,
             exp: 0,
-            next_exp: base,
+            next_exp: curve.required(1),
             lvl: 1,
             tasks: Vec::new(),
             milestones: Vec::new(),
+            streak: 0,
+            best_streak: 0,
+            last_active: None,
+            exp_curve: None,
         }
     }
     pub fn get_uid(&self) -> u64{
@@ -129,6 +251,18 @@ impl Category{
     pub fn set_name(&mut self, name: String){
         self.name = name;
     }
+    pub fn edit_task(&mut self, id: usize, name: String) -> Result<(), ()>{
+        for (old_id, old_task) in self.tasks.iter().enumerate(){
+            if old_task.name == name && old_id != id{
+                return Err(());
+            }
+        }
+        if let Some(task) = self.get_task_mut(id){
+            task.set_name(name);
+            return Ok(());
+        }
+        Err(())
+    }
     pub fn move_task(&mut self, id: usize, by: i32) -> Result<(), ()>{
         if (id as i32 + by < 0) || (id as i32 + by >= self.tasks.len() as i32) {
             return Err(());
@@ -156,7 +290,25 @@ impl Category{
     pub fn get_task(&self, id: usize) -> Option<&Task>{
         self.tasks.get(id)
     }
+    pub fn get_task_mut(&mut self, id: usize) -> Option<&mut Task>{
+        self.tasks.get_mut(id)
+    }
+    pub fn task_position(&self, uid: u64) -> Option<usize>{
+        self.tasks.iter().position(|task| task.get_uid() == uid)
+    }
 
+    pub fn edit_milestone(&mut self, id: usize, name: String) -> Result<(), ()>{
+        for (old_id, old_milestone) in self.milestones.iter().enumerate(){
+            if old_milestone.name == name && old_id != id{
+                return Err(());
+            }
+        }
+        if let Some(milestone) = self.get_milestone_mut(id){
+            milestone.set_name(name);
+            return Ok(());
+        }
+        Err(())
+    }
     pub fn move_milestone(&mut self, id: usize, by: i32) -> Result<(), ()>{
         if (id as i32 + by < 0) || (id as i32 + by >= self.milestones.len() as i32) {
             return Err(());
@@ -184,50 +336,169 @@ impl Category{
     pub fn get_milestone(&self, id: usize) -> Option<&Milestone>{
         self.milestones.get(id)
     }
+    pub fn get_milestone_mut(&mut self, id: usize) -> Option<&mut Milestone>{
+        self.milestones.get_mut(id)
+    }
+    pub fn milestone_position(&self, uid: u64) -> Option<usize>{
+        self.milestones.iter().position(|milestone| milestone.get_uid() == uid)
+    }
 
-    pub fn increase_exp(&mut self, exp: u32, power: f32, base: u32){
+    /// Counts today into the daily streak of the category
+    pub fn register_activity(&mut self){
+        let today = today();
+        match self.last_active.map(local_date){
+            Some(last_active) if last_active == today => {},
+            Some(last_active) if Some(last_active) == today.pred_opt() => self.streak += 1,
+            _ => self.streak = 1,
+        }
+        self.best_streak = self.best_streak.max(self.streak);
+        self.last_active = Some(timestamp());
+    }
+    /// Resets the streak if a whole day passed without activity, returns the lost streak
+    pub fn check_streak(&mut self) -> Option<u32>{
+        let last_active = local_date(self.last_active?);
+        if self.streak > 0 && Some(last_active) < today().pred_opt(){
+            let lost = self.streak;
+            self.streak = 0;
+            return Some(lost);
+        }
+        None
+    }
+
+    /// Curve used by category, `default` unless category overrides it
+    pub fn curve<'a>(&'a self, default: &'a ExpCurve) -> &'a ExpCurve{
+        self.exp_curve.as_ref().unwrap_or(default)
+    }
+    pub fn recalculate(&mut self, curve: &ExpCurve){
+        let (lvl, exp, next_exp) = self.curve(curve).stats(self.exp_sum);
+        self.lvl = lvl;
+        self.exp = exp as u32;
+        self.next_exp = next_exp as u32;
+    }
+    pub fn increase_exp(&mut self, exp: u32, curve: &ExpCurve){
         self.exp_sum += exp as u64;
         self.exp += exp;
-        self.lvl_up(power, base);
+        self.lvl_up(curve);
     }
-    pub fn lvl_up(&mut self, power: f32, base: u32){
-        if self.exp >= self.next_exp{
-            self.exp = self.exp - self.next_exp;
-            self.lvl += 1;
-            self.next_exp = calculate_exp(self.lvl, power, base)
+    pub fn lvl_up(&mut self, curve: &ExpCurve){
+        if self.exp < self.next_exp{
+            return;
         }
+        let (lvl, exp, next_exp) = self.curve(curve).advance(self.lvl, self.exp as u64);
+        self.lvl = lvl;
+        self.exp = exp as u32;
+        self.next_exp = next_exp as u32;
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum TaskStatus{
+    Repeatable,
+    Due,
+    Done,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Task{
+    unique_id: u64,
     pub name: String,
     pub exp_reward: u32,
+
+    pub recurrence: Recurrence,
+    pub last_completed: Option<u64>,
+    pub created_at: u64,
+
+    pub streak: u32,
+    pub best_streak: u32,
 }
 
 impl Task{
     pub fn new() -> Self{
         Self {
+            unique_id: 0,
             name: String::new(),
             exp_reward: 0,
+            recurrence: Recurrence::None,
+            last_completed: None,
+            created_at: timestamp(),
+            streak: 0,
+            best_streak: 0,
         }
     }
     pub fn init(name: String, exp_reward: u32) -> Self{
         Self {
+            unique_id: 0,
             name,
             exp_reward,
+            recurrence: Recurrence::None,
+            last_completed: None,
+            created_at: timestamp(),
+            streak: 0,
+            best_streak: 0,
         }
     }
+    pub fn get_uid(&self) -> u64{
+        self.unique_id
+    }
     pub fn set_name(&mut self, name: String){
         self.name = name;
     }
     pub fn set_exp_revard(&mut self, exp_reward: u32){
         self.exp_reward = exp_reward;
     }
+    pub fn set_recurrence(&mut self, recurrence: Recurrence){
+        self.recurrence = recurrence;
+    }
+    fn period_start(&self) -> Option<NaiveDate>{
+        self.recurrence.period_start(today(), local_date(self.created_at))
+    }
+    fn previous_period_start(&self) -> Option<NaiveDate>{
+        let period_start = self.period_start()?;
+        self.recurrence.period_start(period_start.pred_opt()?, local_date(self.created_at))
+    }
+    pub fn status(&self) -> TaskStatus{
+        let Some(period_start) = self.period_start() else {
+            return TaskStatus::Repeatable;
+        };
+
+        match self.last_completed{
+            Some(last_completed) if local_date(last_completed) >= period_start => TaskStatus::Done,
+            _ => TaskStatus::Due,
+        }
+    }
+    /// Marks task as completed for the current period and extends its streak, fails if it was already done
+    pub fn complete(&mut self) -> Result<(), ()>{
+        match self.status(){
+            TaskStatus::Done => return Err(()),
+            TaskStatus::Due => {
+                let previous_start = self.previous_period_start();
+                match (self.last_completed.map(local_date), previous_start){
+                    (Some(last_completed), Some(previous_start)) if last_completed >= previous_start => self.streak += 1,
+                    _ => self.streak = 1,
+                }
+                self.best_streak = self.best_streak.max(self.streak);
+            }
+            TaskStatus::Repeatable => {}
+        }
+        self.last_completed = Some(timestamp());
+        Ok(())
+    }
+    /// Resets the streak if the previous period was missed, returns the lost streak
+    pub fn check_streak(&mut self) -> Option<u32>{
+        let last_completed = local_date(self.last_completed?);
+        let previous_start = self.previous_period_start()?;
+        if self.streak > 0 && last_completed < previous_start{
+            let lost = self.streak;
+            self.streak = 0;
+            return Some(lost);
+        }
+        None
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Milestone{
+    unique_id: u64,
     pub name: String,
     pub exp_reward: u32,
 }
@@ -235,16 +506,21 @@ pub struct Milestone{
 impl Milestone{
     pub fn new() -> Self{
         Self {
+            unique_id: 0,
             name: String::new(),
             exp_reward: 0,
         }
     }
     pub fn init(name: String, exp_reward: u32) -> Self{
         Self {
+            unique_id: 0,
             name,
             exp_reward,
         }
     }
+    pub fn get_uid(&self) -> u64{
+        self.unique_id
+    }
     pub fn set_name(&mut self, name: String){
         self.name = name;
     }
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::command_parser::{self, Arg, Call};
+
+/// Named command sequence from config, `{param}` in `body` is replaced with the matching argument
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Macro{
+    #[serde(default)]
+    pub params: Vec<String>,
+    pub body: String,
+}
+
+/// Macro called by `name`, names are case insensitive like commands
+pub fn find<'a>(macros: &'a HashMap<String, Macro>, name: &str) -> Option<(&'a String, &'a Macro)>{
+    macros.iter().find(|(macro_name, _)| macro_name.eq_ignore_ascii_case(name))
+}
+
+/// Replaces macro calls with commands from their bodies, `stack` holds macros being expanded.
+/// Every command comes with macros it was expanded from, so its nested commands continue the same check
+pub fn expand(calls: Vec<Call>, macros: &HashMap<String, Macro>, stack: &mut Vec<String>) -> Result<Vec<(Call, Vec<String>)>, String>{
+    let mut expanded: Vec<(Call, Vec<String>)> = Vec::new();
+    for call in calls{
+        let Some((name, macro_data)) = find(macros, &call.name) else {
+            expanded.push((call, stack.clone()));
+            continue;
+        };
+        if stack.contains(name){
+            return Err(format!("macro `{}` calls itself through `{}`", name, stack.join(" -> ")));
+        }
+        if call.args.len() != macro_data.params.len(){
+            return Err(format!("macro `{}` expects {} argument(s), got {}", name, macro_data.params.len(), call.args.len()));
+        }
+
+        let mut body = macro_data.body.clone();
+        for (param, arg) in macro_data.params.iter().zip(call.args.iter()){
+            body = body.replace(&format!("{{{}}}", param), &arg.to_string());
+        }
+        let body_calls = command_parser::parse(&body).map_err(|error| format!("in macro `{}`: {}", name, error))?;
+
+        stack.push(name.to_string());
+        expanded.extend(expand(body_calls, macros, stack)?);
+        stack.pop();
+    }
+    Ok(expanded)
+}
+
+/// Names of commands called in `calls`, including nested ones
+fn called_names(calls: &[Call], names: &mut Vec<String>){
+    for call in calls{
+        names.push(call.name.to_string());
+        for (id, arg) in call.args.iter().enumerate(){
+            match arg{
+                Arg::Call(nested) => called_names(std::slice::from_ref(nested), names),
+                // Quoted `If` branches hold whole command sequences
+                Arg::Str(value) | Arg::Raw(value) if call.key() == "if" && id > 0 => {
+                    if let Ok(nested) = command_parser::parse(value){
+                        called_names(&nested, names);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Checks macro bodies parse, don't shadow commands and don't call each other in a loop
+pub fn validate(macros: &HashMap<String, Macro>, command_names: &[&str]) -> Vec<String>{
+    let mut errors: Vec<String> = Vec::new();
+    let mut calls: HashMap<&String, Vec<String>> = HashMap::new();
+
+    let mut names: Vec<&String> = macros.keys().collect();
+    names.sort();
+    for name in names.iter(){
+        if command_names.iter().any(|command| command.eq_ignore_ascii_case(name)){
+            errors.push(format!("macros.{}: name is already used by a command", name));
+        }
+        match command_parser::parse(&macros[*name].body){
+            Ok(body_calls) => {
+                let mut called: Vec<String> = Vec::new();
+                called_names(&body_calls, &mut called);
+                calls.insert(name, called);
+            }
+            Err(error) => errors.push(format!("macros.{}: {} (body: `{}`)", name, error, macros[*name].body)),
+        }
+    }
+
+    for name in names{
+        let mut path: Vec<String> = vec![name.to_string()];
+        if let Some(cycle) = find_cycle(name, macros, &calls, &mut path){
+            errors.push(format!("macros.{}: recursive macro `{}`", name, cycle.join(" -> ")));
+        }
+    }
+    errors
+}
+
+fn find_cycle(name: &str, macros: &HashMap<String, Macro>, calls: &HashMap<&String, Vec<String>>, path: &mut Vec<String>) -> Option<Vec<String>>{
+    let (_, called) = calls.iter().find(|(macro_name, _)| macro_name.as_str() == name)?;
+    for called_name in called.iter(){
+        let Some((called_macro, _)) = find(macros, called_name) else {
+            continue;
+        };
+        if path.first() == Some(called_macro){
+            let mut cycle = path.clone();
+            cycle.push(called_macro.to_string());
+            return Some(cycle);
+        }
+        // Loops not going through the starting macro are reported for their own macros
+        if path.contains(called_macro){
+            continue;
+        }
+        path.push(called_macro.to_string());
+        if let Some(cycle) = find_cycle(called_macro, macros, calls, path){
+            return Some(cycle);
+        }
+        path.pop();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn macros(definitions: &[(&str, &[&str], &str)]) -> HashMap<String, Macro>{
+        definitions.iter().map(|(name, params, body)| {
+            let params = params.iter().map(|param| param.to_string()).collect();
+            (name.to_string(), Macro { params, body: body.to_string() })
+        }).collect()
+    }
+
+    #[test]
+    fn expands_nested_macros_with_arguments(){
+        let macros = macros(&[
+            ("greet", &["name"], "Notify({name}); Bell"),
+            ("welcome", &[], "Greet(\"a, b\"); State(Main)"),
+        ]);
+        let calls = command_parser::parse("welcome; Quit").unwrap();
+        let expanded = expand(calls, &macros, &mut Vec::new()).unwrap();
+        let lines: Vec<String> = expanded.iter().map(|(call, stack)| format!("{} {}", call, stack.join(">"))).collect();
+        assert_eq!(lines, ["Notify(\"a, b\") welcome>greet", "Bell welcome>greet", "State(Main) welcome", "Quit "]);
+    }
+
+    #[test]
+    fn expand_rejects_recursion_and_wrong_arguments(){
+        let macros = macros(&[("a", &[], "b"), ("b", &[], "Bell; A"), ("one", &["x"], "Notify({x})")]);
+        let error = expand(command_parser::parse("a").unwrap(), &macros, &mut Vec::new()).unwrap_err();
+        assert_eq!(error, "macro `a` calls itself through `a -> b`");
+        let error = expand(command_parser::parse("one").unwrap(), &macros, &mut Vec::new()).unwrap_err();
+        assert_eq!(error, "macro `one` expects 1 argument(s), got 0");
+    }
+
+    #[test]
+    fn validate_finds_loops_through_if_branches(){
+        let macros = macros(&[
+            ("a", &[], "If(eq(1, 1), \"Bell; b\", Quit)"),
+            ("b", &[], "If(eq(1, 1), Bell, a)"),
+            ("fine", &[], "a"),
+        ]);
+        let errors = validate(&macros, &[]);
+        assert_eq!(errors, ["macros.a: recursive macro `a -> b -> a`", "macros.b: recursive macro `b -> a -> b`"]);
+    }
+
+    #[test]
+    fn validate_reports_bad_bodies_and_shadowed_commands(){
+        let macros = macros(&[("quit", &[], "Bell"), ("broken", &[], "Notify(")]);
+        let errors = validate(&macros, &["Quit"]);
+        assert_eq!(errors, [
+            "macros.broken: expected `)`, found end of command at position 7 (body: `Notify(`)",
+            "macros.quit: name is already used by a command",
+        ]);
+    }
+}
@@ -7,33 +7,102 @@ mod timer;
 mod json_types;
 mod theme;
 mod wild_type;
-
-use std::{error::Error, fs::File, io::{self, Read}, path::PathBuf, time::{Duration, Instant}};
+mod schedule;
+mod exp_curve;
+mod migrations;
+mod storage;
+mod command_parser;
+mod command_line;
+mod macros;
+mod condition;
+mod script;
+mod cli;
+mod event_bus;
+mod hooks;
+mod notifications;
+mod undo;
+#[cfg(unix)]
+mod control;
+
+use std::{error::Error, fs::File, io::{self, Read}, panic, path::PathBuf, sync::{Arc, Mutex}, time::{Duration, Instant}};
 
 use dirs_next::config_dir;
 use ratatui::{prelude::Backend, Terminal};
 
-use crate::{app::App, events::handle_events, json_types::Category, ui::ui};
+use crate::{app::{App, AppConfig}, cli::CliCommand, events::handle_events, json_types::Category, json_types::Data, notifications::Severity, ui::ui};
 
 fn get_config_dir(app_name: &str) -> Option<PathBuf> {
     dirs_next::config_dir().map(|base| base.join(app_name))
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match CliCommand::parse(&args){
+        Ok(Some(CliCommand::Help)) => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, cli::USAGE);
+            std::process::exit(1);
+        }
+    };
+
     if let Some(config_path) = get_config_dir("trexp"){
-        println!("{}", config_path.to_str().expect("").to_string());
         let mut file = File::open(config_path.join("config.json").to_str().expect("no config path provided").to_string())?;
         let mut data = String::new();
 
         file.read_to_string(&mut data)?;
 
-        let mut terminal = ratatui::init();
         let mut app = App::new();
 
-        app.load_config(serde_json::from_str(&data).expect("couldnt parse data"));
+        let config: AppConfig = match serde_json::from_str(&data){
+            Ok(config) => config,
+            Err(error) => {
+                eprintln!("Couldn't parse config.json: {}", error);
+                std::process::exit(1);
+            }
+        };
+        let config_errors = config.validate();
+        if !config_errors.is_empty(){
+            eprintln!("Errors in config.json:");
+            for error in config_errors.iter(){
+                eprintln!("  {}", error);
+            }
+            std::process::exit(1);
+        }
+        app.load_config(config);
+
+        if let Some(command) = command{
+            // Running app would overwrite changes made to data file, so it runs the command instead
+            #[cfg(unix)]
+            if let Some(result) = control::forward_cli(&config_path.join("trexp.sock"), &args){
+                return print_cli_result(result);
+            }
+            app.init(config_path);
+            return print_cli_result(cli::run(&mut app, command));
+        }
         app.init(config_path);
+        println!("{}", app.config_path.to_str().expect("").to_string());
 
-        run_app(&mut terminal, &mut app)?;
+        let mut terminal = ratatui::init();
+        let crash_snapshot: Arc<Mutex<Option<Data>>> = Arc::new(Mutex::new(None));
+        install_panic_hook(app.data_path(), Arc::clone(&crash_snapshot));
+
+        let result = run_app(&mut terminal, &mut app, &crash_snapshot);
+        ratatui::restore();
+
+        if let Err(error) = app.save_data(){
+            eprintln!("Couldn't save data on exit: {}", error);
+            if app.data_load_error.is_none(){
+                match App::write_emergency_snapshot(&app.data_path(), &app.data){
+                    Ok(path) => eprintln!("Data written to {} instead", path.display()),
+                    Err(error) => eprintln!("Couldn't write data snapshot: {}", error),
+                }
+            }
+        }
+        result?;
 
         Ok(())
     }
@@ -43,14 +112,68 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<(), io::Error>{
+fn print_cli_result(result: Result<String, String>) -> Result<(), Box<dyn Error>>{
+    match result{
+        Ok(output) => {
+            if !output.is_empty(){
+                println!("{}", output);
+            }
+            Ok(())
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Restores the terminal and writes last data snapshot on panic. Runs before unwinding,
+/// so it also works with `panic = "abort"` and for panics on other threads
+fn install_panic_hook(data_path: PathBuf, snapshot: Arc<Mutex<Option<Data>>>){
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        ratatui::restore();
+        // Lock held by the panicking thread would never be released, snapshot is skipped then
+        if let Ok(snapshot) = snapshot.try_lock() && let Some(data) = snapshot.as_ref(){
+            match App::write_emergency_snapshot(&data_path, data){
+                Ok(path) => eprintln!("trexp crashed, data snapshot written to {}", path.display()),
+                Err(error) => eprintln!("trexp crashed, couldn't write data snapshot: {}", error),
+            }
+        }
+        previous(info);
+    }));
+}
+
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, crash_snapshot: &Mutex<Option<Data>>) -> Result<(), io::Error>{
     let tick_rate = Duration::from_secs_f64(0.25);
     let mut last_tick = Instant::now();
 
+    #[cfg(unix)]
+    let mut control = if app.app_config.control_socket{
+        match control::ControlSocket::bind(app.config_path.join("trexp.sock")){
+            Ok(control) => Some(control),
+            Err(error) => {
+                app.notify(Severity::Error, format!("Couldn't open control socket: {}", error));
+                None
+            }
+        }
+    }else{
+        None
+    };
+
     while !app.exit{
         terminal.draw(|f| ui(f, app))?;
 
         app.handle_timers();
+        app.check_streaks();
+        app.handle_autosave();
+        app.handle_event_bus();
+        app.handle_notifications();
+        app.update_crash_snapshot(crash_snapshot);
+        #[cfg(unix)]
+        if let Some(control) = control.as_mut(){
+            control.handle(app);
+        }
 
         let timeout: Duration = tick_rate
             .checked_sub(last_tick.elapsed())
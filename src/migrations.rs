@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+/// Version of data.json written by this build
+pub const DATA_VERSION: u32 = 1;
+
+pub fn data_version(value: &Value) -> u32{
+    value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32
+}
+
+/// Brings data file content saved by older versions up to `DATA_VERSION`
+pub fn migrate(mut value: Value) -> Result<Value, String>{
+    if !value.is_object(){
+        return Err("data file is not a json object".to_string());
+    }
+
+    let mut version = data_version(&value);
+    if version > DATA_VERSION{
+        return Err(format!("data file version {} is newer than supported version {}", version, DATA_VERSION));
+    }
+
+    while version < DATA_VERSION{
+        value = match version{
+            0 => migrate_v0(value),
+            _ => return Err(format!("no migration from data version {}", version)),
+        };
+        version += 1;
+        value["version"] = Value::from(version);
+    }
+
+    Ok(value)
+}
+
+/// Files from before versioning. Adds fields introduced since then and gives unique ids to
+/// tasks and milestones, which had none, and to categories sharing timestamp based ones
+fn migrate_v0(mut value: Value) -> Value{
+    set_missing(&mut value, "history", Value::Array(Vec::new()));
+    // Empty fingerprint makes levels get recalculated with the current exp curve
+    set_missing(&mut value, "curve_fingerprint", Value::from(""));
+    set_missing(&mut value, "categories", Value::Array(Vec::new()));
+    set_missing(&mut value, "timers", Value::Array(Vec::new()));
+
+    for category in value["categories"].as_array_mut().into_iter().flatten(){
+        set_missing(category, "streak", Value::from(0));
+        set_missing(category, "best_streak", Value::from(0));
+        set_missing(category, "last_active", Value::Null);
+        set_missing(category, "exp_curve", Value::Null);
+        set_missing(category, "tasks", Value::Array(Vec::new()));
+        set_missing(category, "milestones", Value::Array(Vec::new()));
+        for task in category["tasks"].as_array_mut().into_iter().flatten(){
+            set_missing(task, "recurrence", Value::from("None"));
+            set_missing(task, "last_completed", Value::Null);
+            set_missing(task, "created_at", Value::from(0));
+            set_missing(task, "streak", Value::from(0));
+            set_missing(task, "best_streak", Value::from(0));
+        }
+    }
+
+    // Ids are kept where they are unique, history refers to categories by them
+    let mut next_uid = value.get("next_uid").and_then(Value::as_u64).unwrap_or(0).max(1);
+    let mut used: HashSet<u64> = HashSet::new();
+    let mut missing: Vec<(usize, Option<(&str, usize)>)> = Vec::new();
+    for (category_id, category) in value["categories"].as_array().into_iter().flatten().enumerate(){
+        let mut items: Vec<(Option<(&str, usize)>, &Value)> = vec![(None, category)];
+        for list in ["tasks", "milestones"]{
+            items.extend(category[list].as_array().into_iter().flatten().enumerate().map(|(id, item)| (Some((list, id)), item)));
+        }
+        for (position, item) in items{
+            match item.get("unique_id").and_then(Value::as_u64){
+                Some(uid) if uid != 0 && used.insert(uid) => next_uid = next_uid.max(uid + 1),
+                _ => missing.push((category_id, position)),
+            }
+        }
+    }
+    for (category_id, position) in missing{
+        let category = &mut value["categories"][category_id];
+        let item = match position{
+            Some((list, id)) => &mut category[list][id],
+            None => category,
+        };
+        item["unique_id"] = Value::from(next_uid);
+        next_uid += 1;
+    }
+    value["next_uid"] = Value::from(next_uid);
+    value
+}
+
+fn set_missing(object: &mut Value, key: &str, default: Value){
+    if let Some(object) = object.as_object_mut(){
+        object.entry(key).or_insert(default);
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{app::App, json_types::Data};
+
+    /// Data file written before versioning, categories created in the same second share their uid
+    fn baseline_file() -> Value{
+        json!({"categories": [
+            {"unique_id": 1700000000, "name": "A", "exp_sum": 250, "exp": 50, "next_exp": 100, "lvl": 3,
+                "tasks": [{"name": "t", "exp_reward": 5}], "milestones": [{"name": "m", "exp_reward": 50}]},
+            {"unique_id": 1700000000, "name": "B", "exp_sum": 0, "exp": 0, "next_exp": 100, "lvl": 1,
+                "tasks": [{"name": "t", "exp_reward": 1}], "milestones": []},
+        ]})
+    }
+
+    #[test]
+    fn upgrades_baseline_file(){
+        let value = migrate(baseline_file()).unwrap();
+        assert_eq!(data_version(&value), DATA_VERSION);
+        assert_eq!(value["next_uid"], 1700000005);
+        let data: Data = serde_json::from_value(value).unwrap();
+
+        let mut uids: Vec<u64> = Vec::new();
+        for category in data.categories.iter(){
+            uids.push(category.get_uid());
+            uids.extend(category.tasks.iter().map(|task| task.get_uid()));
+            uids.extend(category.milestones.iter().map(|milestone| milestone.get_uid()));
+        }
+        // First category keeps its uid, the rest get new ones after it
+        assert_eq!(uids, [1700000000, 1700000001, 1700000002, 1700000003, 1700000004]);
+        assert_eq!(data.categories[1].tasks[0].recurrence, crate::schedule::Recurrence::None);
+        assert!(data.history.is_empty() && data.timers.is_empty());
+        assert_eq!(data.curve_fingerprint, "");
+    }
+
+    #[test]
+    fn keeps_current_files_unchanged(){
+        let value = serde_json::to_value(Data::new()).unwrap();
+        assert_eq!(migrate(value.clone()).unwrap(), value);
+    }
+
+    #[test]
+    fn refuses_newer_and_invalid_files(){
+        let error = migrate(json!({"version": DATA_VERSION + 1, "categories": []})).unwrap_err();
+        assert_eq!(error, format!("data file version {} is newer than supported version {}", DATA_VERSION + 1, DATA_VERSION));
+        assert_eq!(migrate(json!([])).unwrap_err(), "data file is not a json object");
+    }
+
+    #[test]
+    fn failed_load_blocks_saving(){
+        let dir = std::env::temp_dir().join(format!("trexp_migrations_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let content = json!({"version": DATA_VERSION + 1, "categories": []}).to_string();
+        fs::write(dir.join("data.json"), &content).unwrap();
+
+        let mut app = App::new();
+        app.init(dir.clone());
+        assert!(app.data_load_error.is_some());
+        assert!(app.save_data().is_err());
+        assert_eq!(fs::read_to_string(dir.join("data.json")).unwrap(), content);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,208 @@
+use std::{collections::VecDeque, fmt, time::Instant};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity{
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity{
+    pub fn from_str(value: &str) -> Option<Severity>{
+        match value.to_lowercase().as_str(){
+            "info" => Some(Severity::Info),
+            "success" => Some(Severity::Success),
+            "warning" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            Severity::Info => write!(f, "info"),
+            Severity::Success => write!(f, "success"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ToastPosition{
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Where announcements, like level-ups, go
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AnnounceMode{
+    Toast,
+    History,
+    Off,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NotificationConfig{
+    #[serde(default = "default_position")]
+    pub position: ToastPosition,
+    /// Toasts shown at once, the rest wait in queue
+    #[serde(default = "default_max_visible")]
+    pub max_visible: usize,
+    /// Seconds toasts stay visible, 0 keeps them until dismissed
+    #[serde(default = "default_info_seconds")]
+    pub info_seconds: f32,
+    #[serde(default = "default_info_seconds")]
+    pub success_seconds: f32,
+    #[serde(default = "default_warning_seconds")]
+    pub warning_seconds: f32,
+    #[serde(default = "default_error_seconds")]
+    pub error_seconds: f32,
+    /// Number of past notifications kept for history view
+    #[serde(default = "default_history_limit")]
+    pub history_limit: usize,
+    /// Key opening history view, empty string disables it
+    #[serde(default = "default_history_key")]
+    pub history_key: String,
+    #[serde(default = "default_level_ups")]
+    pub level_ups: AnnounceMode,
+}
+pub fn default_position() -> ToastPosition{ToastPosition::TopRight}
+pub fn default_max_visible() -> usize{3}
+pub fn default_info_seconds() -> f32{4.0}
+pub fn default_warning_seconds() -> f32{8.0}
+pub fn default_error_seconds() -> f32{10.0}
+pub fn default_history_limit() -> usize{100}
+pub fn default_history_key() -> String{"C(N)".to_string()}
+pub fn default_level_ups() -> AnnounceMode{AnnounceMode::Toast}
+
+impl NotificationConfig{
+    pub fn new() -> Self{
+        Self {
+            position: default_position(),
+            max_visible: default_max_visible(),
+            info_seconds: default_info_seconds(),
+            success_seconds: default_info_seconds(),
+            warning_seconds: default_warning_seconds(),
+            error_seconds: default_error_seconds(),
+            history_limit: default_history_limit(),
+            history_key: default_history_key(),
+            level_ups: default_level_ups(),
+        }
+    }
+    pub fn seconds(&self, severity: Severity) -> f32{
+        match severity{
+            Severity::Info => self.info_seconds,
+            Severity::Success => self.success_seconds,
+            Severity::Warning => self.warning_seconds,
+            Severity::Error => self.error_seconds,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Notification{
+    pub id: u64,
+    pub severity: Severity,
+    pub message: String,
+    /// Set when toast gets on screen, expiry counts from then
+    shown_at: Option<Instant>,
+}
+
+/// Toasts waiting or shown on screen and history of past ones
+pub struct Notifications{
+    toasts: VecDeque<Notification>,
+    history: VecDeque<Notification>,
+    next_id: u64,
+    pub history_open: bool,
+    pub history_scroll: usize,
+}
+
+impl Notifications{
+    pub fn new() -> Self{
+        Self {
+            toasts: VecDeque::new(),
+            history: VecDeque::new(),
+            next_id: 0,
+            history_open: false,
+            history_scroll: 0,
+        }
+    }
+
+    /// Adds notification, `toast` false keeps it only in history
+    pub fn push(&mut self, severity: Severity, message: String, toast: bool, history_limit: usize){
+        let notification = Notification { id: self.next_id, severity, message, shown_at: None };
+        self.next_id += 1;
+
+        if toast{
+            self.toasts.push_back(notification.clone());
+        }
+        self.history.push_back(notification);
+        while self.history.len() > history_limit{
+            self.history.pop_front();
+        }
+    }
+
+    /// Id next notification will get, to find ones pushed after
+    pub fn next_id(&self) -> u64{
+        self.next_id
+    }
+
+    /// Shows waiting toasts and removes ones which were visible long enough
+    pub fn update(&mut self, config: &NotificationConfig){
+        let now = Instant::now();
+        for toast in self.toasts.iter_mut().take(config.max_visible){
+            toast.shown_at.get_or_insert(now);
+        }
+        self.toasts.retain(|toast| {
+            let seconds = config.seconds(toast.severity);
+            match toast.shown_at{
+                Some(shown_at) => seconds <= 0.0 || now.duration_since(shown_at).as_secs_f32() < seconds,
+                None => true,
+            }
+        });
+    }
+
+    pub fn visible(&self, config: &NotificationConfig) -> impl Iterator<Item = &Notification>{
+        self.toasts.iter().take(config.max_visible)
+    }
+
+    pub fn waiting(&self, config: &NotificationConfig) -> usize{
+        self.toasts.len().saturating_sub(config.max_visible)
+    }
+
+    pub fn dismiss(&mut self){
+        self.toasts.clear();
+    }
+
+    /// Past notifications, newest first
+    pub fn history(&self) -> impl Iterator<Item = &Notification>{
+        self.history.iter().rev()
+    }
+
+    pub fn history_len(&self) -> usize{
+        self.history.len()
+    }
+
+    pub fn toggle_history(&mut self){
+        self.history_open = !self.history_open;
+        self.history_scroll = 0;
+        // Everything is in history, toasts would only cover it
+        if self.history_open{
+            self.dismiss();
+        }
+    }
+
+    pub fn scroll_history(&mut self, by: i32){
+        let max = self.history.len().saturating_sub(1) as i32;
+        self.history_scroll = (self.history_scroll as i32 + by).clamp(0, max) as usize;
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+
+use chrono::{Datelike, Days, Local, Months, NaiveDate, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+
+pub fn local_date(timestamp: u64) -> NaiveDate{
+    Local.timestamp_opt(timestamp as i64, 0)
+        .single()
+        .expect("invalid timestamp")
+        .date_naive()
+}
+
+pub fn today() -> NaiveDate{
+    Local::now().date_naive()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Recurrence{
+    None,
+    Daily,
+    Weekdays(Vec<Weekday>),
+    EveryDays(u32),
+    Monthly(u32),
+}
+
+impl Recurrence{
+    /// Parses values like "daily", "weekly mon wed", "every 3" or "monthly 15"
+    pub fn from_str(value: &str) -> Option<Recurrence>{
+        let value = value.trim().to_lowercase();
+        let mut parts = value.split_whitespace();
+
+        match parts.next()?{
+            "none" => Some(Recurrence::None),
+            "daily" => Some(Recurrence::Daily),
+            "weekly" => {
+                let mut days: Vec<Weekday> = Vec::new();
+                for part in parts{
+                    let day = part.parse::<Weekday>().ok()?;
+                    if !days.contains(&day){
+                        days.push(day);
+                    }
+                }
+                if days.is_empty(){
+                    return None;
+                }
+                days.sort_by_key(|day| day.num_days_from_monday());
+                Some(Recurrence::Weekdays(days))
+            }
+            "every" => {
+                let days = parts.next()?.parse::<u32>().ok()?;
+                if days == 0{
+                    return None;
+                }
+                Some(Recurrence::EveryDays(days))
+            }
+            "monthly" => {
+                let day = parts.next()?.parse::<u32>().ok()?;
+                if day == 0 || day > 31{
+                    return None;
+                }
+                Some(Recurrence::Monthly(day))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_recurring(&self) -> bool{
+        *self != Recurrence::None
+    }
+
+    /// Days a streak of `periods` spans, so streak bonus grows at the same pace for every schedule
+    pub fn streak_days(&self, periods: u32) -> u32{
+        let period_days = match self{
+            Recurrence::None | Recurrence::Daily => 1.0,
+            Recurrence::Weekdays(days) => 7.0 / days.len().max(1) as f32,
+            Recurrence::EveryDays(every) => *every as f32,
+            Recurrence::Monthly(_) => 30.0,
+        };
+        (periods as f32 * period_days) as u32
+    }
+
+    /// First day of the period containing `date`. `anchor` is the day the schedule started,
+    /// used by schedules counted in days
+    pub fn period_start(&self, date: NaiveDate, anchor: NaiveDate) -> Option<NaiveDate>{
+        match self{
+            Recurrence::None => None,
+            Recurrence::Daily => Some(date),
+            Recurrence::Weekdays(days) => {
+                (0..7).filter_map(|back| date.checked_sub_days(Days::new(back)))
+                    .find(|day| days.contains(&day.weekday()))
+            }
+            Recurrence::EveryDays(every) => {
+                let passed = (date - anchor).num_days();
+                let offset = passed.rem_euclid(*every as i64);
+                date.checked_sub_days(Days::new(offset as u64))
+            }
+            Recurrence::Monthly(day) => {
+                let this_month = month_day(date, *day);
+                if date >= this_month{
+                    Some(this_month)
+                }else{
+                    Some(month_day(date.checked_sub_months(Months::new(1))?, *day))
+                }
+            }
+        }
+    }
+}
+
+/// `day` of the month `date` is in, clamped to the length of that month
+fn month_day(date: NaiveDate, day: u32) -> NaiveDate{
+    let mut day = day;
+    loop{
+        if let Some(value) = date.with_day(day){
+            return value;
+        }
+        day -= 1;
+    }
+}
+
+impl fmt::Display for Recurrence{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            Recurrence::None => write!(f, "none"),
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekdays(days) => {
+                let days: Vec<String> = days.iter().map(|day| day.to_string()).collect();
+                write!(f, "weekly {}", days.join(" "))
+            }
+            Recurrence::EveryDays(every) => write!(f, "every {} days", every),
+            Recurrence::Monthly(day) => write!(f, "monthly {}", day),
+        }
+    }
+}
+
+pub fn format_timestamp(timestamp: u64) -> String{
+    Local.timestamp_opt(timestamp as i64, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,56 @@
+use crate::wild_type::{Generic, Variant};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnaryOp{
+    Neg,
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp{
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Clone, Debug)]
+pub enum Expr{
+    Literal(Variant),
+    Array(Vec<Expr>),
+    Variable(String),
+    Call(String, Vec<Expr>),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Debug)]
+pub enum Stmt{
+    Let(String, Generic, Expr),
+    Assign(String, Expr),
+    If(Expr, Vec<Node>, Vec<Node>),
+    While(Expr, Vec<Node>),
+    For(String, Expr, Vec<Node>),
+    Return(Option<Expr>),
+    Break,
+    Continue,
+    Expr(Expr),
+}
+
+/// Statement with the line it starts on, used in error messages
+#[derive(Clone, Debug)]
+pub struct Node{
+    pub stmt: Stmt,
+    pub line: usize,
+}
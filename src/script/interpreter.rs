@@ -0,0 +1,396 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::{app::App, script::{ast::{BinaryOp, Expr, Node, Stmt, UnaryOp}, natives, ScriptEngine}, wild_type::{Function, Generic, Variable, Variant}};
+
+/// Statements one call may run, keeps endless loops from freezing the app
+pub const MAX_STEPS: usize = 100_000;
+const MAX_DEPTH: usize = 64;
+
+enum Flow{
+    Next,
+    Return(Variant),
+    Break,
+    Continue,
+}
+
+type Scopes = Vec<HashMap<String, Variable>>;
+
+pub struct Interpreter<'a>{
+    engine: &'a ScriptEngine,
+    app: &'a mut App,
+    steps: usize,
+    depth: usize,
+    /// Set for widget functions, which run every frame and can't change the app
+    read_only: bool,
+    /// Line of the statement which failed, reported by the function containing it
+    error_line: Option<usize>,
+    /// Set once the failing function added its name and line to the error
+    error_located: bool,
+}
+
+impl<'a> Interpreter<'a>{
+    pub fn new(engine: &'a ScriptEngine, app: &'a mut App) -> Self{
+        Self {
+            engine,
+            app,
+            steps: 0,
+            depth: 0,
+            read_only: false,
+            error_line: None,
+            error_located: false,
+        }
+    }
+
+    pub fn read_only(mut self) -> Self{
+        self.read_only = true;
+        self
+    }
+
+    pub fn call(&mut self, name: &str, args: Vec<Variant>) -> Result<Variant, String>{
+        let Some(function) = self.engine.functions.get(name) else {
+            return Err(format!("unknown function `{}`", name));
+        };
+        let function = match function{
+            Function::Native(native) => {
+                if self.read_only && natives::MUTATING.contains(&name){
+                    return Err(format!("`{}` can't be used by widget functions", name));
+                }
+                let args = args.into_iter().map(|variant| Variable { variant, generic: Generic::Any }).collect();
+                return native(self.app, args).map(|variable| variable.variant);
+            }
+            Function::Defined(function) => function,
+        };
+
+        if args.len() != function.params.len(){
+            return Err(format!("`{}` expects {} argument(s), got {}", name, function.params.len(), args.len()));
+        }
+        if self.depth >= MAX_DEPTH{
+            return Err(format!("`{}` nested too deep", name));
+        }
+
+        let mut scope: HashMap<String, Variable> = HashMap::new();
+        for ((param, generic), variant) in function.params.iter().zip(args){
+            if !generic.accepts(&variant){
+                return Err(format!("`{}` expects {:?} for `{}`, got {}", name, generic, param, variant.type_name()));
+            }
+            scope.insert(param.to_string(), Variable { variant, generic: generic.clone() });
+        }
+
+        self.depth += 1;
+        let mut scopes: Scopes = vec![scope];
+        let flow = self.block(&function.body, &mut scopes);
+        self.depth -= 1;
+
+        match flow{
+            Ok(Flow::Return(value)) => Ok(value),
+            Ok(_) => Ok(Variant::NULL),
+            Err(error) if self.error_located => Err(error),
+            Err(error) => {
+                self.error_located = true;
+                let line = self.error_line.take().map(|line| format!(", line {}", line)).unwrap_or_default();
+                Err(format!("{} (in `{}`{})", error, name, line))
+            }
+        }
+    }
+
+    fn block(&mut self, nodes: &[Node], scopes: &mut Scopes) -> Result<Flow, String>{
+        scopes.push(HashMap::new());
+        let mut flow = Ok(Flow::Next);
+        for node in nodes{
+            flow = self.node(node, scopes);
+            if !matches!(flow, Ok(Flow::Next)){
+                break;
+            }
+        }
+        scopes.pop();
+        flow
+    }
+
+    /// Counts statements and loop iterations against `MAX_STEPS`
+    fn step(&mut self) -> Result<(), String>{
+        self.steps += 1;
+        if self.steps > MAX_STEPS{
+            return Err(format!("script exceeded {} steps", MAX_STEPS));
+        }
+        Ok(())
+    }
+
+    fn node(&mut self, node: &Node, scopes: &mut Scopes) -> Result<Flow, String>{
+        let result = self.step().and_then(|_| self.stmt(&node.stmt, scopes));
+        if result.is_err() && self.error_line.is_none(){
+            self.error_line = Some(node.line);
+        }
+        result
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, scopes: &mut Scopes) -> Result<Flow, String>{
+        match stmt{
+            Stmt::Let(name, generic, value) => {
+                let variant = self.eval(value, scopes)?;
+                if !generic.accepts(&variant){
+                    return Err(format!("can't store {} in `{}` of type {:?}", variant.type_name(), name, generic));
+                }
+                scopes.last_mut().expect("no scope").insert(name.to_string(), Variable { variant, generic: generic.clone() });
+            }
+            Stmt::Assign(name, value) => {
+                let variant = self.eval(value, scopes)?;
+                let Some(variable) = scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name)) else {
+                    return Err(format!("unknown variable `{}`", name));
+                };
+                if !variable.generic.accepts(&variant){
+                    return Err(format!("can't store {} in `{}` of type {:?}", variant.type_name(), name, variable.generic));
+                }
+                variable.variant = variant;
+            }
+            Stmt::If(condition, then_nodes, else_nodes) => {
+                if self.eval(condition, scopes)?.is_truthy(){
+                    return self.block(then_nodes, scopes);
+                }
+                return self.block(else_nodes, scopes);
+            }
+            Stmt::While(condition, body) => {
+                while self.eval(condition, scopes)?.is_truthy(){
+                    self.step()?;
+                    match self.block(body, scopes)?{
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Next | Flow::Continue => {}
+                    }
+                }
+            }
+            Stmt::For(name, values, body) => {
+                let values = match self.eval(values, scopes)?{
+                    Variant::Array(values, _) => values,
+                    value => return Err(format!("can't loop over {}", value.type_name())),
+                };
+                for variant in values{
+                    scopes.push(HashMap::from([(name.to_string(), Variable { variant, generic: Generic::Any })]));
+                    let flow = self.block(body, scopes);
+                    scopes.pop();
+                    match flow?{
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Next | Flow::Continue => {}
+                    }
+                }
+            }
+            Stmt::Return(value) => {
+                let value = match value{
+                    Some(value) => self.eval(value, scopes)?,
+                    None => Variant::NULL,
+                };
+                return Ok(Flow::Return(value));
+            }
+            Stmt::Break => return Ok(Flow::Break),
+            Stmt::Continue => return Ok(Flow::Continue),
+            Stmt::Expr(value) => {
+                self.eval(value, scopes)?;
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn eval(&mut self, expr: &Expr, scopes: &mut Scopes) -> Result<Variant, String>{
+        match expr{
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Array(values) => {
+                let values = values.iter().map(|value| self.eval(value, scopes)).collect::<Result<Vec<Variant>, String>>()?;
+                Ok(Variant::Array(values, Generic::Any))
+            }
+            Expr::Variable(name) => scopes.iter().rev()
+                .find_map(|scope| scope.get(name))
+                .map(|variable| variable.variant.clone())
+                .ok_or_else(|| format!("unknown variable `{}`", name)),
+            Expr::Call(name, args) => {
+                let args = args.iter().map(|arg| self.eval(arg, scopes)).collect::<Result<Vec<Variant>, String>>()?;
+                self.call(name, args)
+            }
+            Expr::Field(value, field) => match self.eval(value, scopes)?{
+                Variant::UserType(user_type) => user_type.borrow().fields.get(field).cloned()
+                    .ok_or_else(|| format!("{} has no field `{}`", user_type.borrow().name, field)),
+                Variant::Dictionary(values, _) => Ok(values.get(field).cloned().unwrap_or(Variant::NULL)),
+                value => Err(format!("{} has no fields", value.type_name())),
+            },
+            Expr::Index(value, index) => {
+                let value = self.eval(value, scopes)?;
+                let index = self.eval(index, scopes)?;
+                match (value, index){
+                    (Variant::Array(values, _), Variant::Int(id)) => {
+                        let id = if id < 0{ values.len() as i64 + id }else{ id };
+                        usize::try_from(id).ok().and_then(|id| values.get(id).cloned())
+                            .ok_or_else(|| format!("index {} out of range for array of {}", id, values.len()))
+                    }
+                    (Variant::Str(value), Variant::Int(id)) => usize::try_from(id).ok()
+                        .and_then(|id| value.chars().nth(id))
+                        .map(|value| Variant::Str(value.to_string()))
+                        .ok_or_else(|| format!("index {} out of range for string", id)),
+                    (Variant::Dictionary(values, _), Variant::Str(key)) => Ok(values.get(&key).cloned().unwrap_or(Variant::NULL)),
+                    (value, index) => Err(format!("can't index {} with {}", value.type_name(), index.type_name())),
+                }
+            }
+            Expr::Unary(op, value) => {
+                let value = self.eval(value, scopes)?;
+                match (op, value){
+                    (UnaryOp::Not, value) => Ok(Variant::Bool(!value.is_truthy())),
+                    (UnaryOp::Neg, Variant::Int(value)) => value.checked_neg().map(Variant::Int).ok_or_else(|| "integer overflow".to_string()),
+                    (UnaryOp::Neg, Variant::Float(value)) => Ok(Variant::Float(-value)),
+                    (UnaryOp::Neg, value) => Err(format!("can't negate {}", value.type_name())),
+                }
+            }
+            Expr::Binary(BinaryOp::And, left, right) => {
+                if !self.eval(left, scopes)?.is_truthy(){
+                    return Ok(Variant::Bool(false));
+                }
+                Ok(Variant::Bool(self.eval(right, scopes)?.is_truthy()))
+            }
+            Expr::Binary(BinaryOp::Or, left, right) => {
+                if self.eval(left, scopes)?.is_truthy(){
+                    return Ok(Variant::Bool(true));
+                }
+                Ok(Variant::Bool(self.eval(right, scopes)?.is_truthy()))
+            }
+            Expr::Binary(op, left, right) => {
+                let left = self.eval(left, scopes)?;
+                let right = self.eval(right, scopes)?;
+                binary(*op, left, right)
+            }
+        }
+    }
+}
+
+fn as_float(value: &Variant) -> Option<f64>{
+    match value{
+        Variant::Int(value) => Some(*value as f64),
+        Variant::Float(value) => Some(*value),
+        _ => None,
+    }
+}
+
+pub fn equals(left: &Variant, right: &Variant) -> bool{
+    match (left, right){
+        (Variant::Int(left), Variant::Int(right)) => left == right,
+        (Variant::Str(left), Variant::Str(right)) => left == right,
+        (Variant::Bool(left), Variant::Bool(right)) => left == right,
+        (Variant::NULL, Variant::NULL) => true,
+        (Variant::Array(left, _), Variant::Array(right, _)) => {
+            left.len() == right.len() && left.iter().zip(right.iter()).all(|(left, right)| equals(left, right))
+        }
+        (left, right) => match (as_float(left), as_float(right)){
+            (Some(left), Some(right)) => left == right,
+            _ => false,
+        },
+    }
+}
+
+fn compare(left: &Variant, right: &Variant) -> Result<Ordering, String>{
+    let ordering = match (left, right){
+        (Variant::Int(left), Variant::Int(right)) => Some(left.cmp(right)),
+        (Variant::Str(left), Variant::Str(right)) => Some(left.cmp(right)),
+        (left, right) => match (as_float(left), as_float(right)){
+            (Some(left), Some(right)) => left.partial_cmp(&right),
+            _ => None,
+        },
+    };
+    ordering.ok_or_else(|| format!("can't compare {} with {}", left.type_name(), right.type_name()))
+}
+
+fn binary(op: BinaryOp, left: Variant, right: Variant) -> Result<Variant, String>{
+    let overflow = || "integer overflow".to_string();
+    match op{
+        BinaryOp::Eq => return Ok(Variant::Bool(equals(&left, &right))),
+        BinaryOp::Ne => return Ok(Variant::Bool(!equals(&left, &right))),
+        BinaryOp::Lt => return Ok(Variant::Bool(compare(&left, &right)?.is_lt())),
+        BinaryOp::Le => return Ok(Variant::Bool(compare(&left, &right)?.is_le())),
+        BinaryOp::Gt => return Ok(Variant::Bool(compare(&left, &right)?.is_gt())),
+        BinaryOp::Ge => return Ok(Variant::Bool(compare(&left, &right)?.is_ge())),
+        _ => {}
+    }
+
+    match (op, left, right){
+        (BinaryOp::Add, Variant::Array(mut left, generic), Variant::Array(right, _)) => {
+            left.extend(right);
+            Ok(Variant::Array(left, generic))
+        }
+        (BinaryOp::Add, left @ Variant::Str(_), right) | (BinaryOp::Add, left, right @ Variant::Str(_)) => {
+            Ok(Variant::Str(format!("{}{}", left, right)))
+        }
+        (op, Variant::Int(left), Variant::Int(right)) => {
+            let value = match op{
+                BinaryOp::Add => left.checked_add(right).ok_or_else(overflow)?,
+                BinaryOp::Sub => left.checked_sub(right).ok_or_else(overflow)?,
+                BinaryOp::Mul => left.checked_mul(right).ok_or_else(overflow)?,
+                BinaryOp::Div | BinaryOp::Rem if right == 0 => return Err("division by zero".to_string()),
+                BinaryOp::Div => left.checked_div(right).ok_or_else(overflow)?,
+                BinaryOp::Rem => left.checked_rem(right).ok_or_else(overflow)?,
+                _ => unreachable!("comparisons and logic are handled earlier"),
+            };
+            Ok(Variant::Int(value))
+        }
+        (op, left, right) => {
+            let (Some(left_value), Some(right_value)) = (as_float(&left), as_float(&right)) else {
+                return Err(format!("can't apply {:?} to {} and {}", op, left.type_name(), right.type_name()));
+            };
+            let value = match op{
+                BinaryOp::Add => left_value + right_value,
+                BinaryOp::Sub => left_value - right_value,
+                BinaryOp::Mul => left_value * right_value,
+                BinaryOp::Div => left_value / right_value,
+                BinaryOp::Rem => left_value % right_value,
+                _ => unreachable!("comparisons and logic are handled earlier"),
+            };
+            Ok(Variant::Float(value))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn run(source: &str, name: &str) -> Result<Variant, String>{
+        let mut engine = ScriptEngine::new();
+        engine.load("test", source)?;
+        engine.call(&mut App::new(), name, Vec::new())
+    }
+
+    #[test]
+    fn runs_loops_and_functions(){
+        let source = "fn add(a: int, b: int) { return a + b; }\n\
+            fn sum() { let total = 0; for id in range(0, 10) { if id == 5 { continue; } total = add(total, id); } return total; }\n\
+            fn count() { let id = 0; while true { id = id + 1; if id >= 3 { break; } } return str(id) + \"!\"; }";
+        assert_eq!(run(source, "sum").unwrap().to_string(), "40");
+        assert_eq!(run(source, "count").unwrap().to_string(), "3!");
+    }
+
+    #[test]
+    fn stops_endless_loops(){
+        let error = run("fn spin() {\n    while true { }\n}", "spin").unwrap_err();
+        assert!(error.contains(&format!("script exceeded {} steps", MAX_STEPS)), "{}", error);
+        let error = run("fn big() { return len(range(0, 1000000)); }", "big").unwrap_err();
+        assert!(error.contains(&format!("range can't have more than {} items", MAX_STEPS)), "{}", error);
+    }
+
+    #[test]
+    fn stops_deep_recursion(){
+        let error = run("fn down(n: int) { return down(n + 1); }\nfn start() { return down(0); }", "start").unwrap_err();
+        assert!(error.contains("`down` nested too deep"), "{}", error);
+        // Depth is released after every call
+        let source = "fn one() { return 1; }\nfn many() { let total = 0; for id in range(0, 200) { total = total + one(); } return total; }";
+        assert_eq!(run(source, "many").unwrap().to_string(), "200");
+    }
+
+    #[test]
+    fn read_only_calls_refuse_mutating_natives(){
+        let mut engine = ScriptEngine::new();
+        engine.load("test", "fn widget() { add_exp(0, 5); return 1; }").unwrap();
+        let error = engine.call_read_only(&mut App::new(), "widget", Vec::new()).unwrap_err();
+        assert!(error.contains("`add_exp` can't be used by widget functions"), "{}", error);
+    }
+
+    #[test]
+    fn reports_parse_and_runtime_errors(){
+        assert!(run("fn broken() { return 1; ", "broken").unwrap_err().starts_with("test: line 1: expected"));
+        assert_eq!(run("fn a() { return 1; }", "missing").unwrap_err(), "unknown function `missing`");
+        assert!(run("fn a() { return x; }", "a").is_err());
+    }
+}
@@ -0,0 +1,234 @@
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token{
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Ident(String),
+    // keywords
+    Fn,
+    Let,
+    If,
+    Else,
+    While,
+    For,
+    In,
+    Return,
+    Break,
+    Continue,
+    True,
+    False,
+    Null,
+    // punctuation
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Comma,
+    Semicolon,
+    Colon,
+    Dot,
+    // operators
+    Assign,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Bang,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    Eof,
+}
+
+#[derive(Clone, Debug)]
+pub struct Spanned{
+    pub token: Token,
+    pub line: usize,
+}
+
+fn keyword(word: &str) -> Option<Token>{
+    let token = match word{
+        "fn" => Token::Fn,
+        "let" => Token::Let,
+        "if" => Token::If,
+        "else" => Token::Else,
+        "while" => Token::While,
+        "for" => Token::For,
+        "in" => Token::In,
+        "return" => Token::Return,
+        "break" => Token::Break,
+        "continue" => Token::Continue,
+        "true" => Token::True,
+        "false" => Token::False,
+        "null" => Token::Null,
+        _ => return None,
+    };
+    Some(token)
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Spanned>, String>{
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens: Vec<Spanned> = Vec::new();
+    let mut line = 1;
+    let mut id = 0;
+
+    while id < chars.len(){
+        let value = chars[id];
+        let next = chars.get(id + 1).copied();
+
+        if value == '\n'{
+            line += 1;
+            id += 1;
+            continue;
+        }
+        if value.is_whitespace(){
+            id += 1;
+            continue;
+        }
+        if value == '/' && next == Some('/'){
+            while id < chars.len() && chars[id] != '\n'{
+                id += 1;
+            }
+            continue;
+        }
+
+        let token = if value.is_ascii_digit(){
+            let start = id;
+            while id < chars.len() && chars[id].is_ascii_digit(){
+                id += 1;
+            }
+            let is_float = id + 1 < chars.len() && chars[id] == '.' && chars[id + 1].is_ascii_digit();
+            if is_float{
+                id += 1;
+                while id < chars.len() && chars[id].is_ascii_digit(){
+                    id += 1;
+                }
+            }
+            let text: String = chars[start..id].iter().collect();
+            if is_float{
+                Token::Float(text.parse::<f64>().map_err(|_| format!("line {}: invalid number `{}`", line, text))?)
+            }else{
+                Token::Int(text.parse::<i64>().map_err(|_| format!("line {}: invalid number `{}`", line, text))?)
+            }
+        }else if value.is_alphabetic() || value == '_'{
+            let start = id;
+            while id < chars.len() && (chars[id].is_alphanumeric() || chars[id] == '_'){
+                id += 1;
+            }
+            let word: String = chars[start..id].iter().collect();
+            keyword(&word).unwrap_or(Token::Ident(word))
+        }else if value == '"'{
+            let start_line = line;
+            let mut text = String::new();
+            id += 1;
+            loop{
+                let Some(value) = chars.get(id) else {
+                    return Err(format!("line {}: unclosed string", start_line));
+                };
+                id += 1;
+                match value{
+                    '"' => break,
+                    '\\' => {
+                        let escaped = match chars.get(id){
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some('"') => '"',
+                            Some('\\') => '\\',
+                            Some(value) => return Err(format!("line {}: unknown escape `\\{}`", line, value)),
+                            None => return Err(format!("line {}: unclosed string", start_line)),
+                        };
+                        text.push(escaped);
+                        id += 1;
+                    }
+                    '\n' => {
+                        line += 1;
+                        text.push('\n');
+                    }
+                    value => text.push(*value),
+                }
+            }
+            tokens.push(Spanned { token: Token::Str(text), line: start_line });
+            continue;
+        }else{
+            let two = |token: Token| (token, 2);
+            let one = |token: Token| (token, 1);
+            let (token, length) = match (value, next){
+                ('=', Some('=')) => two(Token::Eq),
+                ('!', Some('=')) => two(Token::Ne),
+                ('<', Some('=')) => two(Token::Le),
+                ('>', Some('=')) => two(Token::Ge),
+                ('&', Some('&')) => two(Token::And),
+                ('|', Some('|')) => two(Token::Or),
+                ('=', _) => one(Token::Assign),
+                ('!', _) => one(Token::Bang),
+                ('<', _) => one(Token::Lt),
+                ('>', _) => one(Token::Gt),
+                ('+', _) => one(Token::Plus),
+                ('-', _) => one(Token::Minus),
+                ('*', _) => one(Token::Star),
+                ('/', _) => one(Token::Slash),
+                ('%', _) => one(Token::Percent),
+                ('(', _) => one(Token::LParen),
+                (')', _) => one(Token::RParen),
+                ('{', _) => one(Token::LBrace),
+                ('}', _) => one(Token::RBrace),
+                ('[', _) => one(Token::LBracket),
+                (']', _) => one(Token::RBracket),
+                (',', _) => one(Token::Comma),
+                (';', _) => one(Token::Semicolon),
+                (':', _) => one(Token::Colon),
+                ('.', _) => one(Token::Dot),
+                _ => return Err(format!("line {}: unexpected character `{}`", line, value)),
+            };
+            id += length;
+            token
+        };
+        tokens.push(Spanned { token, line });
+    }
+
+    tokens.push(Spanned { token: Token::Eof, line });
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<Token>{
+        tokenize(source).unwrap().into_iter().map(|spanned| spanned.token).collect()
+    }
+
+    #[test]
+    fn tokenizes_keywords_numbers_and_operators(){
+        assert_eq!(tokens("let x = 1.5 >= 2 && !done; // comment"), [
+            Token::Let, Token::Ident("x".to_string()), Token::Assign, Token::Float(1.5), Token::Ge, Token::Int(2),
+            Token::And, Token::Bang, Token::Ident("done".to_string()), Token::Semicolon, Token::Eof,
+        ]);
+        // Dot after number without digits is field access
+        assert_eq!(tokens("1.len"), [Token::Int(1), Token::Dot, Token::Ident("len".to_string()), Token::Eof]);
+    }
+
+    #[test]
+    fn strings_handle_escapes_and_lines(){
+        let spanned = tokenize("\"a\\\"b\\n\\\\\"\n\"two\nlines\" x").unwrap();
+        assert_eq!(spanned[0].token, Token::Str("a\"b\n\\".to_string()));
+        assert_eq!((spanned[1].token.clone(), spanned[1].line), (Token::Str("two\nlines".to_string()), 2));
+        assert_eq!(spanned[2].line, 3);
+    }
+
+    #[test]
+    fn reports_errors(){
+        assert_eq!(tokenize("\n\"open").unwrap_err(), "line 2: unclosed string");
+        assert_eq!(tokenize("\"\\q\"").unwrap_err(), "line 1: unknown escape `\\q`");
+        assert_eq!(tokenize("a # b").unwrap_err(), "line 1: unexpected character `#`");
+        assert_eq!(tokenize("99999999999999999999").unwrap_err(), "line 1: invalid number `99999999999999999999`");
+    }
+}
@@ -0,0 +1,41 @@
+pub mod lexer;
+pub mod ast;
+pub mod parser;
+pub mod interpreter;
+pub mod natives;
+
+use std::collections::HashMap;
+
+use crate::{app::App, script::interpreter::Interpreter, wild_type::{Function, Variant}};
+
+/// Native bindings and functions defined by loaded scripts
+pub struct ScriptEngine{
+    functions: HashMap<String, Function>,
+}
+
+impl ScriptEngine{
+    pub fn new() -> Self{
+        Self { functions: natives::functions() }
+    }
+
+    /// Adds functions defined in `source`, `origin` names the source in errors
+    pub fn load(&mut self, origin: &str, source: &str) -> Result<(), String>{
+        let functions = parser::parse(source).map_err(|error| format!("{}: {}", origin, error))?;
+        for (name, function) in functions{
+            if self.functions.contains_key(&name){
+                return Err(format!("{}: function `{}` is already defined", origin, name));
+            }
+            self.functions.insert(name, Function::Defined(function));
+        }
+        Ok(())
+    }
+
+    pub fn call(&self, app: &mut App, name: &str, args: Vec<Variant>) -> Result<Variant, String>{
+        Interpreter::new(self, app).call(name, args)
+    }
+
+    /// Calls function without natives which change the app, used for widgets
+    pub fn call_read_only(&self, app: &mut App, name: &str, args: Vec<Variant>) -> Result<Variant, String>{
+        Interpreter::new(self, app).read_only().call(name, args)
+    }
+}
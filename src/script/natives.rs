@@ -0,0 +1,245 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{app::App, script::interpreter::MAX_STEPS, json_types::{timestamp, Category, ExpSource, Task, TaskStatus}, notifications::Severity, wild_type::{Function, Generic, UserType, Variable, Variant}};
+
+type Native = fn(&mut App, Vec<Variable>) -> Result<Variable, String>;
+
+/// Natives changing app state, refused to read-only calls
+pub const MUTATING: &[&str] = &["result", "error", "run", "set", "add_exp", "set_task_exp"];
+
+/// Functions scripts can call to read and change the app
+pub fn functions() -> HashMap<String, Function>{
+    let natives: Vec<(&str, Native)> = vec![
+        ("len", len),
+        ("str", to_str),
+        ("int", to_int),
+        ("float", to_float),
+        ("range", range),
+        ("now", now),
+        ("result", result),
+        ("error", error),
+        ("run", run),
+        ("get", get),
+        ("set", set),
+        ("fact", fact),
+        ("exp_required", exp_required),
+        ("category_count", category_count),
+        ("category", category),
+        ("selected_category", selected_category),
+        ("task", task),
+        ("selected_task", selected_task),
+        ("add_exp", add_exp),
+        ("set_task_exp", set_task_exp),
+    ];
+    natives.into_iter().map(|(name, native)| (name.to_string(), Function::Native(native))).collect()
+}
+
+fn value(variant: Variant) -> Result<Variable, String>{
+    Ok(Variable { variant, generic: Generic::Any })
+}
+
+fn expect_count(args: &[Variable], count: usize) -> Result<(), String>{
+    if args.len() != count{
+        return Err(format!("expected {} argument(s), got {}", count, args.len()));
+    }
+    Ok(())
+}
+
+fn int_arg(args: &[Variable], id: usize) -> Result<i64, String>{
+    match args.get(id).map(|arg| &arg.variant){
+        Some(Variant::Int(value)) => Ok(*value),
+        Some(value) => Err(format!("expected int as argument {}, got {}", id + 1, value.type_name())),
+        None => Err(format!("missing argument {}", id + 1)),
+    }
+}
+
+fn index_arg(args: &[Variable], id: usize) -> Result<usize, String>{
+    usize::try_from(int_arg(args, id)?).map_err(|_| format!("argument {} can't be negative", id + 1))
+}
+
+fn text_arg(args: &[Variable], id: usize) -> Result<String, String>{
+    args.get(id).map(|arg| arg.variant.to_string()).ok_or_else(|| format!("missing argument {}", id + 1))
+}
+
+fn object(name: &str, fields: Vec<(&str, Variant)>) -> Variant{
+    let fields = fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect();
+    Variant::UserType(Rc::new(RefCell::new(UserType { name: name.to_string(), fields })))
+}
+
+fn category_object(category: &Category) -> Variant{
+    object("Category", vec![
+        ("uid", Variant::Int(category.get_uid() as i64)),
+        ("name", Variant::Str(category.name.to_string())),
+        ("lvl", Variant::Int(category.lvl as i64)),
+        ("exp", Variant::Int(category.exp as i64)),
+        ("next_exp", Variant::Int(category.next_exp as i64)),
+        ("exp_sum", Variant::Int(category.exp_sum as i64)),
+        ("streak", Variant::Int(category.streak as i64)),
+        ("best_streak", Variant::Int(category.best_streak as i64)),
+        ("task_count", Variant::Int(category.tasks.len() as i64)),
+        ("milestone_count", Variant::Int(category.milestones.len() as i64)),
+    ])
+}
+
+fn task_object(task: &Task) -> Variant{
+    object("Task", vec![
+        ("uid", Variant::Int(task.get_uid() as i64)),
+        ("name", Variant::Str(task.name.to_string())),
+        ("exp", Variant::Int(task.exp_reward as i64)),
+        ("recurrence", Variant::Str(task.recurrence.to_string())),
+        ("done", Variant::Bool(task.status() == TaskStatus::Done)),
+        ("streak", Variant::Int(task.streak as i64)),
+        ("best_streak", Variant::Int(task.best_streak as i64)),
+    ])
+}
+
+fn len(_app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 1)?;
+    let length = match &args[0].variant{
+        Variant::Str(value) => value.chars().count(),
+        Variant::Array(values, _) => values.len(),
+        Variant::Dictionary(values, _) => values.len(),
+        value => return Err(format!("{} has no length", value.type_name())),
+    };
+    value(Variant::Int(length as i64))
+}
+
+fn to_str(_app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 1)?;
+    value(Variant::Str(text_arg(&args, 0)?))
+}
+
+fn to_int(_app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 1)?;
+    let number = match &args[0].variant{
+        Variant::Int(value) => *value,
+        Variant::Float(value) => *value as i64,
+        Variant::Bool(value) => *value as i64,
+        Variant::Str(value) => value.trim().parse::<i64>().map_err(|_| format!("`{}` is not an int", value))?,
+        value => return Err(format!("can't convert {} to int", value.type_name())),
+    };
+    value(Variant::Int(number))
+}
+
+fn to_float(_app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 1)?;
+    let number = match &args[0].variant{
+        Variant::Int(value) => *value as f64,
+        Variant::Float(value) => *value,
+        Variant::Str(value) => value.trim().parse::<f64>().map_err(|_| format!("`{}` is not a float", value))?,
+        value => return Err(format!("can't convert {} to float", value.type_name())),
+    };
+    value(Variant::Float(number))
+}
+
+/// Ints from first argument up to, but without, the second one
+fn range(_app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 2)?;
+    let (start, end) = (int_arg(&args, 0)?, int_arg(&args, 1)?);
+    // Looping over more than that would exceed step limit anyway
+    if (end as i128 - start as i128) > MAX_STEPS as i128{
+        return Err(format!("range can't have more than {} items", MAX_STEPS));
+    }
+    let values = (start..end).map(Variant::Int).collect();
+    value(Variant::Array(values, Generic::Int))
+}
+
+fn now(_app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 0)?;
+    value(Variant::Int(timestamp() as i64))
+}
+
+fn result(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 1)?;
+    app.notify(Severity::Info, text_arg(&args, 0)?);
+    value(Variant::NULL)
+}
+
+fn error(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 1)?;
+    app.notify(Severity::Error, text_arg(&args, 0)?);
+    value(Variant::NULL)
+}
+
+/// Runs app commands, like keybinds do
+fn run(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 1)?;
+    app.run_command_string(text_arg(&args, 0)?);
+    value(Variant::NULL)
+}
+
+fn get(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 1)?;
+    value(app.additional_data.get(&text_arg(&args, 0)?).cloned().unwrap_or(Variant::NULL))
+}
+
+fn set(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 2)?;
+    app.additional_data.insert(text_arg(&args, 0)?, args[1].variant.clone());
+    value(Variant::NULL)
+}
+
+fn fact(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 1)?;
+    value(Variant::from_string(&app.fact(&text_arg(&args, 0)?), &Generic::Any))
+}
+
+fn exp_required(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 1)?;
+    let lvl = u32::try_from(int_arg(&args, 0)?).map_err(|_| "level out of range".to_string())?;
+    value(Variant::Int(app.app_config.exp_curve().required(lvl) as i64))
+}
+
+fn category_count(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 0)?;
+    value(Variant::Int(app.data.categories.len() as i64))
+}
+
+fn category(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 1)?;
+    let category = app.data.categories.get(index_arg(&args, 0)?);
+    value(category.map(category_object).unwrap_or(Variant::NULL))
+}
+
+fn selected_category(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 0)?;
+    value(app.selected_category().map(|category_id| Variant::Int(category_id as i64)).unwrap_or(Variant::NULL))
+}
+
+fn task(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 2)?;
+    let task = app.data.categories.get(index_arg(&args, 0)?)
+        .and_then(|category| category.tasks.get(index_arg(&args, 1).ok()?));
+    value(task.map(task_object).unwrap_or(Variant::NULL))
+}
+
+/// Category and task position as `[category, task]`
+fn selected_task(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 0)?;
+    let selected = app.selected_task().map(|(category_id, task_id)| {
+        Variant::Array(vec![Variant::Int(category_id as i64), Variant::Int(task_id as i64)], Generic::Int)
+    });
+    value(selected.unwrap_or(Variant::NULL))
+}
+
+/// Gives exp to category at position, returns its new level
+fn add_exp(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 2)?;
+    let exp = u32::try_from(int_arg(&args, 1)?).map_err(|_| "exp can't be negative".to_string())?;
+    let Some(category) = app.data.categories.get(index_arg(&args, 0)?) else {
+        return Err("no category at position".to_string());
+    };
+    let event = app.give_exp(category.get_uid(), exp, ExpSource::Manual);
+    value(event.map(|event| Variant::Int(event.lvl_after as i64)).unwrap_or(Variant::NULL))
+}
+
+fn set_task_exp(app: &mut App, args: Vec<Variable>) -> Result<Variable, String>{
+    expect_count(&args, 3)?;
+    let exp = u32::try_from(int_arg(&args, 2)?).map_err(|_| "exp can't be negative".to_string())?;
+    let task_id = index_arg(&args, 1)?;
+    let Some(task) = app.data.categories.get_mut(index_arg(&args, 0)?).and_then(|category| category.tasks.get_mut(task_id)) else {
+        return Err("no task at position".to_string());
+    };
+    task.exp_reward = exp;
+    value(Variant::NULL)
+}
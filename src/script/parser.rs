@@ -0,0 +1,291 @@
+use crate::{script::{ast::{BinaryOp, Expr, Node, Stmt, UnaryOp}, lexer::{tokenize, Spanned, Token}}, wild_type::{DefinedFunction, Generic, Variant}};
+
+struct Parser{
+    tokens: Vec<Spanned>,
+    position: usize,
+}
+
+impl Parser{
+    fn peek(&self) -> &Token{
+        &self.tokens[self.position].token
+    }
+    fn line(&self) -> usize{
+        self.tokens[self.position].line
+    }
+    fn advance(&mut self) -> Token{
+        let token = self.tokens[self.position].token.clone();
+        if token != Token::Eof{
+            self.position += 1;
+        }
+        token
+    }
+    fn check(&mut self, token: &Token) -> bool{
+        if self.peek() == token{
+            self.advance();
+            return true;
+        }
+        false
+    }
+    fn error(&self, expected: &str) -> String{
+        format!("line {}: expected {}, found {:?}", self.line(), expected, self.peek())
+    }
+    fn expect(&mut self, token: Token, expected: &str) -> Result<(), String>{
+        if self.check(&token){
+            return Ok(());
+        }
+        Err(self.error(expected))
+    }
+    fn ident(&mut self) -> Result<String, String>{
+        if let Token::Ident(name) = self.peek().clone(){
+            self.advance();
+            return Ok(name);
+        }
+        Err(self.error("name"))
+    }
+    /// Optional `: type` annotation
+    fn generic(&mut self) -> Result<Generic, String>{
+        if !self.check(&Token::Colon){
+            return Ok(Generic::Any);
+        }
+        let line = self.line();
+        let name = self.ident()?;
+        match name.as_str(){
+            "int" | "float" | "bool" | "string" | "any" => Ok(Generic::from_string(&name)),
+            _ => Err(format!("line {}: unknown type `{}`", line, name)),
+        }
+    }
+
+    fn function(&mut self) -> Result<(String, DefinedFunction), String>{
+        self.expect(Token::Fn, "`fn`")?;
+        let name = self.ident()?;
+        self.expect(Token::LParen, "`(`")?;
+        let mut params: Vec<(String, Generic)> = Vec::new();
+        if !self.check(&Token::RParen){
+            loop{
+                let param = self.ident()?;
+                params.push((param, self.generic()?));
+                if self.check(&Token::RParen){
+                    break;
+                }
+                self.expect(Token::Comma, "`,` or `)`")?;
+            }
+        }
+        let body = self.block()?;
+        Ok((name, DefinedFunction { params, body }))
+    }
+
+    fn block(&mut self) -> Result<Vec<Node>, String>{
+        self.expect(Token::LBrace, "`{`")?;
+        let mut nodes: Vec<Node> = Vec::new();
+        while !self.check(&Token::RBrace){
+            if *self.peek() == Token::Eof{
+                return Err(self.error("`}`"));
+            }
+            nodes.push(self.statement()?);
+        }
+        Ok(nodes)
+    }
+
+    fn statement(&mut self) -> Result<Node, String>{
+        let line = self.line();
+        let stmt = match self.peek().clone(){
+            Token::Let => {
+                self.advance();
+                let name = self.ident()?;
+                let generic = self.generic()?;
+                self.expect(Token::Assign, "`=`")?;
+                let value = self.expression()?;
+                self.expect(Token::Semicolon, "`;`")?;
+                Stmt::Let(name, generic, value)
+            }
+            Token::If => {
+                self.advance();
+                self.if_statement()?
+            }
+            Token::While => {
+                self.advance();
+                let condition = self.expression()?;
+                Stmt::While(condition, self.block()?)
+            }
+            Token::For => {
+                self.advance();
+                let name = self.ident()?;
+                self.expect(Token::In, "`in`")?;
+                let values = self.expression()?;
+                Stmt::For(name, values, self.block()?)
+            }
+            Token::Return => {
+                self.advance();
+                let value = if *self.peek() == Token::Semicolon{
+                    None
+                }else{
+                    Some(self.expression()?)
+                };
+                self.expect(Token::Semicolon, "`;`")?;
+                Stmt::Return(value)
+            }
+            Token::Break => {
+                self.advance();
+                self.expect(Token::Semicolon, "`;`")?;
+                Stmt::Break
+            }
+            Token::Continue => {
+                self.advance();
+                self.expect(Token::Semicolon, "`;`")?;
+                Stmt::Continue
+            }
+            _ => {
+                let value = self.expression()?;
+                let stmt = if self.check(&Token::Assign){
+                    let Expr::Variable(name) = value else {
+                        return Err(format!("line {}: only variables can be assigned", line));
+                    };
+                    Stmt::Assign(name, self.expression()?)
+                }else{
+                    Stmt::Expr(value)
+                };
+                self.expect(Token::Semicolon, "`;`")?;
+                stmt
+            }
+        };
+        Ok(Node { stmt, line })
+    }
+
+    /// Rest of `if` after the keyword, `else if` chains nest in the else branch
+    fn if_statement(&mut self) -> Result<Stmt, String>{
+        let condition = self.expression()?;
+        let then_nodes = self.block()?;
+        let mut else_nodes: Vec<Node> = Vec::new();
+        if self.check(&Token::Else){
+            if *self.peek() == Token::If{
+                let line = self.line();
+                self.advance();
+                else_nodes.push(Node { stmt: self.if_statement()?, line });
+            }else{
+                else_nodes = self.block()?;
+            }
+        }
+        Ok(Stmt::If(condition, then_nodes, else_nodes))
+    }
+
+    fn expression(&mut self) -> Result<Expr, String>{
+        self.binary(0)
+    }
+
+    /// Operators by precedence, loosest first
+    fn binary_op(token: &Token) -> Option<(BinaryOp, usize)>{
+        let op = match token{
+            Token::Or => (BinaryOp::Or, 0),
+            Token::And => (BinaryOp::And, 1),
+            Token::Eq => (BinaryOp::Eq, 2),
+            Token::Ne => (BinaryOp::Ne, 2),
+            Token::Lt => (BinaryOp::Lt, 3),
+            Token::Le => (BinaryOp::Le, 3),
+            Token::Gt => (BinaryOp::Gt, 3),
+            Token::Ge => (BinaryOp::Ge, 3),
+            Token::Plus => (BinaryOp::Add, 4),
+            Token::Minus => (BinaryOp::Sub, 4),
+            Token::Star => (BinaryOp::Mul, 5),
+            Token::Slash => (BinaryOp::Div, 5),
+            Token::Percent => (BinaryOp::Rem, 5),
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    fn binary(&mut self, min_precedence: usize) -> Result<Expr, String>{
+        let mut left = self.unary()?;
+        while let Some((op, precedence)) = Parser::binary_op(self.peek()){
+            if precedence < min_precedence{
+                break;
+            }
+            self.advance();
+            let right = self.binary(precedence + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String>{
+        if self.check(&Token::Minus){
+            return Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.unary()?)));
+        }
+        if self.check(&Token::Bang){
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(self.unary()?)));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String>{
+        let mut value = self.primary()?;
+        loop{
+            if self.check(&Token::Dot){
+                value = Expr::Field(Box::new(value), self.ident()?);
+            }else if self.check(&Token::LBracket){
+                let index = self.expression()?;
+                self.expect(Token::RBracket, "`]`")?;
+                value = Expr::Index(Box::new(value), Box::new(index));
+            }else{
+                return Ok(value);
+            }
+        }
+    }
+
+    fn arguments(&mut self, close: Token, expected: &str) -> Result<Vec<Expr>, String>{
+        let mut values: Vec<Expr> = Vec::new();
+        if self.check(&close){
+            return Ok(values);
+        }
+        loop{
+            values.push(self.expression()?);
+            if self.check(&close){
+                return Ok(values);
+            }
+            self.expect(Token::Comma, expected)?;
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String>{
+        let value = match self.peek().clone(){
+            Token::Int(value) => Expr::Literal(Variant::Int(value)),
+            Token::Float(value) => Expr::Literal(Variant::Float(value)),
+            Token::Str(value) => Expr::Literal(Variant::Str(value)),
+            Token::True => Expr::Literal(Variant::Bool(true)),
+            Token::False => Expr::Literal(Variant::Bool(false)),
+            Token::Null => Expr::Literal(Variant::NULL),
+            Token::Ident(name) => {
+                self.advance();
+                if self.check(&Token::LParen){
+                    return Ok(Expr::Call(name, self.arguments(Token::RParen, "`,` or `)`")?));
+                }
+                return Ok(Expr::Variable(name));
+            }
+            Token::LParen => {
+                self.advance();
+                let value = self.expression()?;
+                self.expect(Token::RParen, "`)`")?;
+                return Ok(value);
+            }
+            Token::LBracket => {
+                self.advance();
+                return Ok(Expr::Array(self.arguments(Token::RBracket, "`,` or `]`")?));
+            }
+            _ => return Err(self.error("value")),
+        };
+        self.advance();
+        Ok(value)
+    }
+}
+
+/// Parses script source made of `fn` definitions
+pub fn parse(source: &str) -> Result<Vec<(String, DefinedFunction)>, String>{
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+    let mut functions: Vec<(String, DefinedFunction)> = Vec::new();
+    while *parser.peek() != Token::Eof{
+        functions.push(parser.function()?);
+    }
+    Ok(functions)
+}
@@ -0,0 +1,172 @@
+use std::{fs::{self, File}, io::{self, Write}, path::{Path, PathBuf}, time::SystemTime};
+
+use chrono::Local;
+
+/// Writes content to a temporary file next to `path` and renames it into place,
+/// so `path` always holds either the old or the new content
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()>{
+    let mut tmp_name = path.file_name().ok_or_else(|| io::Error::other("path has no file name"))?.to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)
+}
+
+/// Modification time of `path`, None when it doesn't exist
+pub fn modified(path: &Path) -> Option<SystemTime>{
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn backup_prefix(path: &Path) -> String{
+    format!("{}.", path.file_name().and_then(|name| name.to_str()).unwrap_or("data.json"))
+}
+
+/// Backups of `path`, newest first
+pub fn list_backups(path: &Path) -> io::Result<Vec<PathBuf>>{
+    let prefix = backup_prefix(path);
+    let dir = path.parent().ok_or_else(|| io::Error::other("path has no parent directory"))?;
+
+    let mut backups: Vec<PathBuf> = Vec::new();
+    for entry in fs::read_dir(dir)?{
+        let entry_path = entry?.path();
+        if let Some(name) = entry_path.file_name().and_then(|name| name.to_str())
+            && name.starts_with(&prefix) && name.ends_with(".bak"){
+            backups.push(entry_path);
+        }
+    }
+    backups.sort();
+    backups.reverse();
+    Ok(backups)
+}
+
+/// Copies current content of `path` to a timestamped backup and removes backups above `keep`
+pub fn create_backup(path: &Path, keep: usize) -> io::Result<()>{
+    if keep == 0 || !path.exists(){
+        return Ok(());
+    }
+
+    let backup_name = format!("{}{}.bak", backup_prefix(path), Local::now().format("%Y%m%d-%H%M%S-%3f"));
+    fs::copy(path, path.with_file_name(backup_name))?;
+
+    for old_backup in list_backups(path)?.into_iter().skip(keep){
+        fs::remove_file(old_backup)?;
+    }
+    Ok(())
+}
+
+/// Copies current content of `path` aside before a backup replaces it, outside of the backup list
+/// so restoring doesn't shift backup indexes
+pub fn keep_before_restore(path: &Path) -> io::Result<()>{
+    if !path.exists(){
+        return Ok(());
+    }
+    fs::copy(path, path.with_file_name(format!("{}before-restore", backup_prefix(path))))?;
+    Ok(())
+}
+
+/// Writes content next to `path` without touching it, used when app state can't be trusted
+pub fn write_emergency_snapshot(path: &Path, content: &[u8]) -> io::Result<PathBuf>{
+    let snapshot_name = format!("{}{}.emergency", backup_prefix(path), Local::now().format("%Y%m%d-%H%M%S-%3f"));
+    let snapshot_path = path.with_file_name(snapshot_name);
+    write_atomic(&snapshot_path, content)?;
+    Ok(snapshot_path)
+}
+
+#[cfg(test)]
+mod tests{
+    use std::{thread::sleep, time::Duration};
+
+    use super::*;
+    use crate::{app::{App, AppCommands}, cli::{self, CliCommand}};
+
+    fn temp_dir(name: &str) -> PathBuf{
+        let dir = std::env::temp_dir().join(format!("trexp_storage_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file_names(dir: &Path) -> Vec<String>{
+        let mut names: Vec<String> = fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn writes_atomically(){
+        let dir = temp_dir("atomic");
+        let path = dir.join("data.json");
+        write_atomic(&path, b"old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(file_names(&dir), ["data.json"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keeps_newest_backups(){
+        let dir = temp_dir("rotation");
+        let path = dir.join("data.json");
+        for id in 1..=6{
+            create_backup(&path, 3).unwrap();
+            write_atomic(&path, id.to_string().as_bytes()).unwrap();
+            // Backup names have millisecond precision
+            sleep(Duration::from_millis(2));
+        }
+        keep_before_restore(&path).unwrap();
+
+        let backups: Vec<String> = list_backups(&path).unwrap().iter().map(|backup| fs::read_to_string(backup).unwrap()).collect();
+        assert_eq!(backups, ["5", "4", "3"]);
+        assert_eq!(file_names(&dir).len(), 5);
+
+        create_backup(&path, 0).unwrap();
+        assert_eq!(list_backups(&path).unwrap().len(), 3);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn restores_backups_by_index_without_rotating(){
+        let dir = temp_dir("restore");
+        let mut app = App::new();
+        app.config_path = dir.clone();
+        for id in 1..=4{
+            app.run_command(&AppCommands::AddCategory(format!("C{}", id)));
+            app.save_data().unwrap();
+            sleep(Duration::from_millis(2));
+        }
+        let backups = list_backups(&app.data_path()).unwrap();
+        assert_eq!(backups.len(), 3);
+
+        app.restore_backup(1).unwrap();
+        assert_eq!(app.data.categories.len(), 2);
+        assert_eq!(list_backups(&app.data_path()).unwrap(), backups);
+        let replaced: String = fs::read_to_string(dir.join("data.json.before-restore")).unwrap();
+        assert!(replaced.contains("\"C4\""));
+
+        app.restore_backup(0).unwrap();
+        assert_eq!(app.data.categories.len(), 3);
+        assert!(app.restore_backup(3).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn command_line_writes_keep_backups(){
+        let dir = temp_dir("cli");
+        let mut app = App::new();
+        app.config_path = dir.clone();
+        app.run_command(&AppCommands::AddCategory("Work".to_string()));
+        app.save_data().unwrap();
+        for _ in 0..3{
+            let command = CliCommand::Log { category: "Work".to_string(), minutes: 1 };
+            cli::run(&mut app, command).unwrap();
+        }
+        assert!(list_backups(&app.data_path()).unwrap().is_empty());
+        assert_eq!(App::read_data(&app.data_path()).unwrap().history.len(), 3);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
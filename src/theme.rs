@@ -57,6 +57,9 @@ pub struct Theme {
     pub selection: Style,
     pub floating: Style,
     pub error: Style,
+    pub info: Style,
+    pub success: Style,
+    pub warning: Style,
     pub help_text: Style,
     pub help_key: Style,
     pub gauge_style: GaugeStyle,
@@ -100,6 +103,21 @@ impl Theme {
                 .bg(Color::Rgb(60, 0, 0))
                 .add_modifier(Modifier::BOLD),
 
+            info: Style::default()
+                .fg(Color::White)
+                .bg(Color::Rgb(20, 20, 30))
+                .add_modifier(Modifier::BOLD),
+
+            success: Style::default()
+                .fg(Color::White)
+                .bg(Color::Rgb(0, 45, 10))
+                .add_modifier(Modifier::BOLD),
+
+            warning: Style::default()
+                .fg(Color::White)
+                .bg(Color::Rgb(70, 45, 0))
+                .add_modifier(Modifier::BOLD),
+
             help_text: Style::default()
                 .fg(Color::Gray)
                 .bg(Color::Rgb(25, 25, 25)),
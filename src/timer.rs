@@ -1,38 +1,384 @@
-use crate::{app::App, json_types::Category};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{app::App, json_types::{timestamp, Category, PomodoroRecord, TimerRecord}};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PomodoroConfig{
+    #[serde(default = "default_work_minutes")]
+    pub work_minutes: f32,
+    #[serde(default = "default_short_break_minutes")]
+    pub short_break_minutes: f32,
+    #[serde(default = "default_long_break_minutes")]
+    pub long_break_minutes: f32,
+    /// Work phases in cycle, the last one is followed by long break
+    #[serde(default = "default_cycle_count")]
+    pub cycle_count: u32,
+    /// Exp given for every finished cycle
+    #[serde(default = "default_cycle_bonus")]
+    pub cycle_bonus: u32,
+    /// Rings terminal bell when phase changes
+    #[serde(default = "default_bell")]
+    pub bell: bool,
+}
+pub fn default_work_minutes() -> f32{25.0}
+pub fn default_short_break_minutes() -> f32{5.0}
+pub fn default_long_break_minutes() -> f32{15.0}
+pub fn default_cycle_count() -> u32{4}
+pub fn default_cycle_bonus() -> u32{20}
+pub fn default_bell() -> bool{true}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase{
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl fmt::Display for Phase{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            Phase::Work => write!(f, "work"),
+            Phase::ShortBreak => write!(f, "short break"),
+            Phase::LongBreak => write!(f, "long break"),
+        }
+    }
+}
+
+impl PomodoroConfig{
+    pub fn new() -> Self{
+        Self {
+            work_minutes: default_work_minutes(),
+            short_break_minutes: default_short_break_minutes(),
+            long_break_minutes: default_long_break_minutes(),
+            cycle_count: default_cycle_count(),
+            cycle_bonus: default_cycle_bonus(),
+            bell: default_bell(),
+        }
+    }
+
+    /// Phases of one cycle with their length in seconds
+    fn phases(&self) -> Vec<(Phase, f32)>{
+        let mut phases: Vec<(Phase, f32)> = Vec::new();
+        for work in 1..=self.cycle_count.max(1){
+            phases.push((Phase::Work, self.phase_seconds(Phase::Work)));
+            if work < self.cycle_count.max(1){
+                phases.push((Phase::ShortBreak, self.phase_seconds(Phase::ShortBreak)));
+            }
+        }
+        phases.push((Phase::LongBreak, self.phase_seconds(Phase::LongBreak)));
+        phases
+    }
+
+    pub fn phase_seconds(&self, phase: Phase) -> f32{
+        match phase{
+            Phase::Work => self.work_minutes * 60.0,
+            Phase::ShortBreak => self.short_break_minutes * 60.0,
+            Phase::LongBreak => self.long_break_minutes * 60.0,
+        }
+    }
+
+    pub fn cycle_seconds(&self) -> f32{
+        self.phases().iter().map(|(_, seconds)| seconds).sum()
+    }
+
+    /// Phase at `active` seconds since pomodoro start, with its number in cycle and seconds left
+    pub fn phase_at(&self, active: f32) -> (Phase, usize, f32){
+        let cycle = self.cycle_seconds();
+        let mut position = if cycle > 0.0 { active % cycle } else { 0.0 };
+        let mut work_number = 0;
+        for (phase, seconds) in self.phases(){
+            if phase == Phase::Work{
+                work_number += 1;
+            }
+            if position < seconds{
+                return (phase, work_number, seconds - position);
+            }
+            position -= seconds;
+        }
+        (Phase::LongBreak, work_number, 0.0)
+    }
+
+    /// Seconds spent in work phases during first `active` seconds
+    pub fn work_seconds(&self, active: f32) -> f32{
+        let cycle = self.cycle_seconds();
+        if cycle <= 0.0{
+            return 0.0;
+        }
+        let full_cycles = (active / cycle).floor();
+        let mut work = full_cycles * self.phase_seconds(Phase::Work) * self.cycle_count.max(1) as f32;
+        let mut position = active - full_cycles * cycle;
+        for (phase, seconds) in self.phases(){
+            let done = position.min(seconds);
+            if phase == Phase::Work{
+                work += done;
+            }
+            position -= done;
+        }
+        work
+    }
+
+    pub fn finished_cycles(&self, active: f32) -> u32{
+        let cycle = self.cycle_seconds();
+        if cycle <= 0.0 { 0 } else { (active / cycle) as u32 }
+    }
+}
+
+/// Progress of pomodoro timer, exp is given for work time not yet rewarded
+#[derive(Clone)]
+pub struct Pomodoro{
+    pub rewarded_exp: u32,
+    pub rewarded_cycles: u32,
+    pub phase: Phase,
+}
 
 pub struct Timer{
     pub category_id: u64,
     pub category_name: String,
+    /// Unix seconds timer was started at, kept across restarts
+    pub started_at: u64,
     start_time: std::time::Instant,
     timer_duration: f32,
     collected_exp: u32,
+    /// Seconds timer ran while app was closed, waiting for user to decide about their exp
+    pub unclaimed: u64,
+    /// Seconds of current interval done when timer was paused
+    paused: Option<f32>,
+    /// Active seconds from before timer was restored
+    offset: f32,
+    pub pomodoro: Option<Pomodoro>,
 }
 
 impl Timer{
     pub fn new(category: &Category, timer_duration: f32) -> Self{
+        Self::restore(category, timer_duration, timestamp())
+    }
+
+    /// Timer continuing one started at `started_at`, exp for time before now is handled by caller
+    pub fn restore(category: &Category, timer_duration: f32, started_at: u64) -> Self{
         Self{
             category_id: category.get_uid().clone(),
             category_name: category.name.clone(),
+            started_at,
             start_time: std::time::Instant::now(),
             timer_duration,
             collected_exp: 0,
+            unclaimed: 0,
+            paused: None,
+            offset: 0.0,
+            pomodoro: None,
+        }
+    }
+
+    pub fn new_pomodoro(category: &Category, timer_duration: f32) -> Self{
+        let mut timer = Self::new(category, timer_duration);
+        timer.pomodoro = Some(Pomodoro { rewarded_exp: 0, rewarded_cycles: 0, phase: Phase::Work });
+        timer
+    }
+
+    /// Continues pomodoro from its saved progress
+    pub fn restore_pomodoro(&mut self, record: &PomodoroRecord, config: &PomodoroConfig){
+        self.offset = record.active_seconds;
+        let phase = config.phase_at(record.active_seconds).0;
+        self.pomodoro = Some(Pomodoro { rewarded_exp: record.rewarded_exp, rewarded_cycles: record.rewarded_cycles, phase });
+    }
+
+    /// Counts `seconds` as active time, used for time timer ran while app was closed
+    pub fn advance(&mut self, seconds: f32){
+        self.offset += seconds;
+    }
+
+    /// Saved form of timer, exp for time after `credited_until` wasn't given yet
+    pub fn record(&self) -> TimerRecord{
+        // Current interval is saved as `paused` progress or as part of time after `credited_until`
+        let pomodoro = self.pomodoro.as_ref().map(|pomodoro| PomodoroRecord {
+            active_seconds: self.offset + self.collected_exp as f32 * self.timer_duration,
+            rewarded_exp: pomodoro.rewarded_exp,
+            rewarded_cycles: pomodoro.rewarded_cycles,
+        });
+        if let Some(progress) = self.paused{
+            return TimerRecord { category_uid: self.category_id, started_at: self.started_at, credited_until: timestamp(), paused: Some(progress), pomodoro };
+        }
+        TimerRecord {
+            category_uid: self.category_id,
+            started_at: self.started_at,
+            credited_until: timestamp().saturating_sub(self.start_time.elapsed().as_secs() + self.unclaimed),
+            paused: None,
+            pomodoro,
         }
     }
 
+    /// Seconds of current interval
     pub fn get_second(&self) -> f32{
-        self.start_time.elapsed().as_secs_f32()
+        self.paused.unwrap_or_else(|| self.start_time.elapsed().as_secs_f32())
     }
 
     pub fn get_minutes(&self) -> u32{
-        (self.start_time.elapsed().as_secs() / 60) as u32
+        (self.get_second() / 60.0) as u32
     }
 
-    pub fn is_finished(&mut self) -> bool{
-        if self.start_time.elapsed().as_secs_f32() > self.timer_duration{
-            self.collected_exp += 1;
-            self.start_time = std::time::Instant::now();
-            return true;
+    /// Seconds timer was running, without pauses
+    pub fn active_seconds(&self) -> f32{
+        self.offset + self.collected_exp as f32 * self.timer_duration + self.get_second()
+    }
+
+    /// Current pomodoro phase, its number in cycle and seconds left
+    pub fn pomodoro_phase(&self, config: &PomodoroConfig) -> Option<(Phase, usize, f32)>{
+        self.pomodoro.as_ref().map(|_| config.phase_at(self.active_seconds()))
+    }
+
+    pub fn is_paused(&self) -> bool{
+        self.paused.is_some()
+    }
+
+    /// Stops counting, keeping progress of current interval. False when already paused
+    pub fn pause(&mut self) -> bool{
+        if self.paused.is_some(){
+            return false;
+        }
+        self.paused = Some(self.start_time.elapsed().as_secs_f32());
+        true
+    }
+
+    /// Continues counting from progress kept by `pause`. False when not paused
+    pub fn resume(&mut self) -> bool{
+        let Some(progress) = self.paused.take() else {
+            return false;
+        };
+        let now = std::time::Instant::now();
+        self.start_time = now.checked_sub(std::time::Duration::from_secs_f32(progress)).unwrap_or(now);
+        true
+    }
+
+    /// Intervals in `seconds` timer ran while app was closed, the rest is carried into the current interval
+    pub fn credit(&mut self, seconds: u64) -> u32{
+        if self.timer_duration <= 0.0{
+            return 0;
+        }
+        let duration = self.timer_duration as f64;
+        let intervals = (seconds as f64 / duration).floor();
+        let remainder = std::time::Duration::from_secs_f64(seconds as f64 - intervals * duration);
+        match self.paused.as_mut(){
+            Some(progress) => *progress += remainder.as_secs_f32(),
+            None => self.start_time = self.start_time.checked_sub(remainder).unwrap_or(self.start_time),
+        }
+        intervals as u32
+    }
+
+    /// Makes restored timer paused with `progress` seconds of interval done
+    pub fn restore_paused(&mut self, progress: f32){
+        self.paused = Some(progress);
+    }
+
+    /// Intervals finished since last call, rest of elapsed time is carried into the current interval
+    pub fn finished_intervals(&mut self) -> u32{
+        if self.paused.is_some() || self.timer_duration <= 0.0{
+            return 0;
+        }
+        let duration = self.timer_duration as f64;
+        let intervals = (self.start_time.elapsed().as_secs_f64() / duration).floor() as u32;
+        if intervals > 0{
+            self.collected_exp += intervals;
+            // Moving start by whole intervals keeps the remainder, unlike restarting from now
+            self.start_time += std::time::Duration::from_secs_f64(intervals as f64 * duration);
         }
-        false
+        intervals
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{app::App, exp_curve::ExpCurve};
+
+    /// Timer giving exp every 15 seconds, started `seconds` ago
+    fn timer_started(seconds: u64) -> Timer{
+        let category = Category::init("A", &ExpCurve::Power { base: 100, power: 1.0 });
+        let mut timer = Timer::new(&category, 15.0);
+        timer.start_time -= Duration::from_secs(seconds);
+        timer
+    }
+
+    fn wait(timer: &mut Timer, seconds: u64){
+        timer.start_time -= Duration::from_secs(seconds);
+    }
+
+    #[test]
+    fn credits_every_missed_interval(){
+        let mut timer = timer_started(50);
+        assert_eq!(timer.finished_intervals(), 3);
+        assert_eq!(timer.collected_exp, 3);
+        assert!((timer.get_second() - 5.0).abs() < 1.0, "{}", timer.get_second());
+        assert_eq!(timer.finished_intervals(), 0);
+    }
+
+    #[test]
+    fn carries_remainder_across_calls(){
+        let mut timer = timer_started(20);
+        assert_eq!(timer.finished_intervals(), 1);
+        wait(&mut timer, 12);
+        assert_eq!(timer.finished_intervals(), 1);
+        wait(&mut timer, 13);
+        // 45 seconds in total
+        assert_eq!(timer.finished_intervals(), 1);
+        assert_eq!(timer.collected_exp, 3);
+    }
+
+    #[test]
+    fn credit_carries_remainder_into_current_interval(){
+        let mut timer = timer_started(0);
+        assert_eq!(timer.credit(52), 3);
+        assert_eq!(timer.finished_intervals(), 0);
+        wait(&mut timer, 9);
+        assert_eq!(timer.finished_intervals(), 1);
+        assert_eq!(timer_started(0).credit(14), 0);
+    }
+
+    #[test]
+    fn paused_time_is_not_counted(){
+        let mut timer = timer_started(10);
+        assert!(timer.pause());
+        wait(&mut timer, 100);
+        assert_eq!(timer.finished_intervals(), 0);
+
+        // Credit while paused adds to progress of the interval
+        assert_eq!(timer.credit(20), 1);
+        assert!(timer.resume());
+        assert!((timer.get_second() - 15.0).abs() < 1.0, "{}", timer.get_second());
+        assert_eq!(timer.finished_intervals(), 1);
+        assert_eq!(timer.finished_intervals(), 0);
+    }
+
+    #[test]
+    fn zero_frequency_gives_nothing(){
+        let category = Category::init("A", &ExpCurve::Power { base: 100, power: 1.0 });
+        let mut timer = Timer::new(&category, 0.0);
+        timer.start_time -= Duration::from_secs(100);
+        assert_eq!(timer.finished_intervals(), 0);
+        assert_eq!(timer.credit(100), 0);
+    }
+
+    #[test]
+    fn pomodoro_counts_only_work_time(){
+        let config = PomodoroConfig::new();
+        // Work, short break and half of the next work phase
+        assert_eq!(config.work_seconds(42.5 * 60.0), 37.5 * 60.0);
+        assert_eq!(config.phase_at(27.0 * 60.0).0, Phase::ShortBreak);
+        assert_eq!(config.finished_cycles(config.cycle_seconds()), 1);
+
+        let mut app = App::new();
+        app.app_config.pomodoro.bell = false;
+        app.run_command(&crate::app::AppCommands::AddCategory("A".to_string()));
+        let mut timer = Timer::new_pomodoro(&app.data.categories[0], app.app_config.timer_frequency);
+        // Whole first work phase and short break
+        timer.advance(30.0 * 60.0);
+        app.timers.push(timer);
+        app.handle_timers();
+        assert_eq!(app.data.categories[0].exp_sum, (25.0 * 60.0 / app.app_config.timer_frequency) as u64);
+        app.handle_timers();
+        assert_eq!(app.data.categories[0].exp_sum, 100);
     }
 }
@@ -35,7 +35,11 @@ impl TrWidget for CategoriesWidget{
             let size: u16 = area.width - 6;
             for (i, category) in app.data.categories.iter().enumerate(){
 
-                let label_left = format!("{}", category.name);
+                let label_left = if category.best_streak > 0{
+                    format!("{} {}d/{}d", category.name, category.streak, category.best_streak)
+                }else{
+                    category.name.to_string()
+                };
                 let label_right = format!("{}/{} : {}", category.exp, category.next_exp, category.lvl);
                 let ratio = category.exp as f32 / category.next_exp as f32;
 
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use ratatui::{layout::Rect, style::Stylize, symbols::border, text::Line, widgets::{Block, Clear, Padding, Paragraph, Wrap}, Frame};
+
+use crate::{app::App, json_types::ExpSource, schedule::format_timestamp, theme::StyleData, traits::tr_widget::TrWidget, ui::{centered_rect, widgets::{ConstraintFit, WidgetData}}};
+
+const HISTORY_LENGTH: usize = 5;
+
+pub struct CategoryPreviewWidget{}
+
+impl CategoryPreviewWidget{
+    pub fn new() -> Self{Self {}}
+}
+
+fn format_duration(seconds: u64) -> String{
+    format!("{}h {}m", seconds / 3600, seconds % 3600 / 60)
+}
+
+impl TrWidget for CategoryPreviewWidget{
+    fn render(&self, frame: &mut Frame, app: &App, layout_data: &HashMap<String, Vec<Rect>>, data: &WidgetData) {
+        let area = match data.constraint_fit{
+            ConstraintFit::Default => layout_data.get(&data.layout).expect("no layout with provided id").get(data.constraint).expect("no constraint with provided id"),
+            ConstraintFit::Centered { percent_x, percent_y } => &centered_rect(percent_x, percent_y, *layout_data.get(&data.layout).expect("no layout with provided id").get(data.constraint).expect("no constraint with provided id"))
+        };
+
+        let mut line_vec: Vec<Line> = Vec::new();
+
+        if let Some(category_id) = app.selected_category() && let Some(category) = app.data.get_category(category_id){
+            let tracked_seconds: u64 = app.data.category_history(category.get_uid())
+                .map(|event| match event.source{
+                    ExpSource::Timer { seconds } => seconds,
+                    _ => 0,
+                })
+                .sum();
+
+            line_vec.push(Line::from(format!("{} (#{})", category.name, category.get_uid()).bold()));
+            line_vec.push(Line::from(""));
+            line_vec.push(Line::from(format!("Level: {}", category.lvl)));
+            line_vec.push(Line::from(format!("Total exp: {}", category.exp_sum)));
+            line_vec.push(Line::from(format!("Next level: {}/{} ({} to go)", category.exp, category.next_exp, category.next_exp.saturating_sub(category.exp))));
+            line_vec.push(Line::from(format!("Tasks: {}   Milestones: {}", category.tasks.len(), category.milestones.len())));
+            line_vec.push(Line::from(format!("Streak: {} days (best {})", category.streak, category.best_streak)));
+            line_vec.push(Line::from(format!("Time tracked: {}", format_duration(tracked_seconds))));
+            line_vec.push(Line::from(""));
+            line_vec.push(Line::from("Recent history".bold()));
+
+            for event in app.data.category_history(category.get_uid()).rev().take(HISTORY_LENGTH){
+                let lvl_text = if event.lvl_after != event.lvl_before{
+                    format!(" lvl {} -> {}", event.lvl_before, event.lvl_after)
+                }else{
+                    String::new()
+                };
+                line_vec.push(Line::from(format!("{}  +{} {}{}", format_timestamp(event.timestamp), event.amount, event.source, lvl_text)));
+            }
+        }
+        else{
+            line_vec.push(Line::from("No category selected"));
+        }
+
+        let style_data: &StyleData = if let Some(data) = data.styles.get(&app.state){
+            app.app_config.styles.get(data).expect("No style with provided name")
+        }else if let Some(data) = data.styles.get("_"){
+            app.app_config.styles.get(data).expect("No default style provoded")
+        }else{
+            &StyleData::new()
+        };
+
+        let style = style_data.to_style();
+
+        let block = Block::bordered()
+            .title(Line::from(" Category ".bold()))
+            .border_set(border::PLAIN)
+            .padding(Padding::new(2, 4, 1, 1))
+            .style(style);
+
+        let paragraph = Paragraph::new(line_vec).block(block).wrap(Wrap { trim: true }).style(style);
+        frame.render_widget(Clear, *area);
+        frame.render_widget(paragraph, *area);
+    }
+}
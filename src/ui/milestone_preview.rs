@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use ratatui::{layout::Rect, style::Stylize, symbols::border, text::Line, widgets::{Block, Clear, Padding, Paragraph, Wrap}, Frame};
+
+use crate::{app::App, theme::StyleData, traits::tr_widget::TrWidget, ui::{centered_rect, widgets::{ConstraintFit, WidgetData}}};
+
+pub struct MilestonePreviewWidget{}
+
+impl MilestonePreviewWidget{
+    pub fn new() -> Self{Self {}}
+}
+
+impl TrWidget for MilestonePreviewWidget{
+    fn render(&self, frame: &mut Frame, app: &App, layout_data: &HashMap<String, Vec<Rect>>, data: &WidgetData) {
+        let area = match data.constraint_fit{
+            ConstraintFit::Default => layout_data.get(&data.layout).expect("no layout with provided id").get(data.constraint).expect("no constraint with provided id"),
+            ConstraintFit::Centered { percent_x, percent_y } => &centered_rect(percent_x, percent_y, *layout_data.get(&data.layout).expect("no layout with provided id").get(data.constraint).expect("no constraint with provided id"))
+        };
+
+        let mut line_vec: Vec<Line> = Vec::new();
+
+        if let Some((category_id, milestone_id)) = app.selected_milestone() && let Some(category) = app.data.get_category(category_id) && let Some(milestone) = category.get_milestone(milestone_id){
+            let levels_after = category.curve(&app.app_config.exp_curve()).stats(category.exp_sum + milestone.exp_reward as u64).0;
+
+            line_vec.push(Line::from(format!("{} (#{})", milestone.name, milestone.get_uid()).bold()));
+            line_vec.push(Line::from(""));
+            line_vec.push(Line::from(format!("Category: {}", category.name)));
+            line_vec.push(Line::from(format!("Exp reward: {}", milestone.exp_reward)));
+            line_vec.push(Line::from(format!("Level after completion: {} -> {}", category.lvl, levels_after)));
+        }
+        else{
+            line_vec.push(Line::from("No milestone selected"));
+        }
+
+        let style_data: &StyleData = if let Some(data) = data.styles.get(&app.state){
+            app.app_config.styles.get(data).expect("No style with provided name")
+        }else if let Some(data) = data.styles.get("_"){
+            app.app_config.styles.get(data).expect("No default style provoded")
+        }else{
+            &StyleData::new()
+        };
+
+        let style = style_data.to_style();
+
+        let block = Block::bordered()
+            .title(Line::from(" Milestone ".bold()))
+            .border_set(border::PLAIN)
+            .padding(Padding::new(2, 4, 1, 1))
+            .style(style);
+
+        let paragraph = Paragraph::new(line_vec).block(block).wrap(Wrap { trim: true }).style(style);
+        frame.render_widget(Clear, *area);
+        frame.render_widget(paragraph, *area);
+    }
+}
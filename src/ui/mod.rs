@@ -7,9 +7,14 @@ pub mod milestones;
 pub mod input;
 pub mod timers;
 pub mod user;
+pub mod category_preview;
+pub mod task_preview;
+pub mod milestone_preview;
+pub mod script;
+pub mod notifications;
 
 
-use ratatui::{layout::{Constraint, Direction, Layout, Rect}, symbols::border, widgets::{Block, Clear, Padding, Paragraph}, Frame};
+use ratatui::{layout::{Constraint, Direction, Layout, Position, Rect}, symbols::border, text::{Line, Span}, widgets::{Block, Clear, Padding, Paragraph}, Frame};
 
 use crate::app::App;
 
@@ -19,34 +24,65 @@ pub fn ui(frame: &mut Frame, app: &mut App){
     app.render_widgets(frame);
 }
 
-pub fn render_result(app: &mut App, frame: &mut Frame, width: u16, height: u16, area: Rect){
+/// Asks whether time restored timers ran while app was closed gives exp
+pub fn render_timer_claim(app: &mut App, frame: &mut Frame, area: Rect){
+    let mut lines: Vec<Line> = vec![Line::from("Timers kept running while trexp was closed:"), Line::from("")];
+    for timer in app.timers.iter().filter(|timer| timer.unclaimed > 0){
+        let exp = (timer.unclaimed as f32 / app.app_config.timer_frequency) as u32;
+        lines.push(Line::from(format!("{}: {}h {}m, {} exp", timer.category_name, timer.unclaimed / 3600, timer.unclaimed % 3600 / 60, exp)));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(" y ", app.theme.help_key),
+        Span::raw(" give exp  "),
+        Span::styled(" n ", app.theme.help_key),
+        Span::raw(" discard"),
+    ]));
+
     let block = Block::bordered()
+        .title(" restored timers ")
         .border_set(border::ROUNDED)
         .padding(Padding::new(3, 3, 1, 1));
 
-    let paragraph = Paragraph::new(app.result_message.clone())
+    let paragraph = Paragraph::new(lines)
         .centered()
         .block(block)
         .style(app.theme.floating);
 
-    let area = centered_rect(width, height, area);
+    let area = centered_rect(60, 40, area);
     frame.render_widget(Clear, area);
     frame.render_widget(paragraph, area);
 }
 
-pub fn render_error(app: &mut App, frame: &mut Frame, width: u16, height: u16, area: Rect){
+pub fn render_command_line(app: &mut App, frame: &mut Frame, area: Rect){
+    let mut lines: Vec<Line> = vec![Line::from(vec![
+        Span::styled(":", app.theme.help_key),
+        Span::raw(app.command_line.input.clone()),
+    ])];
+    if let Some(error) = &app.command_line.error{
+        lines.push(Line::styled(error.clone(), app.theme.error));
+    }
+    if let Some(completions) = app.command_line.completions(){
+        lines.push(Line::styled(completions.join("  "), app.theme.help_text));
+    }
+
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let area = Rect::new(area.x, area.y + area.height - height, area.width, height);
+
     let block = Block::bordered()
+        .title("command")
         .border_set(border::ROUNDED)
-        .padding(Padding::new(3, 3, 1, 1));
+        .padding(Padding::horizontal(1));
 
-    let paragraph = Paragraph::new(app.error_message.clone())
-        .centered()
+    let paragraph = Paragraph::new(lines)
         .block(block)
-        .style(app.theme.error);
+        .style(app.theme.floating);
 
-    let area = centered_rect(width, height, area);
     frame.render_widget(Clear, area);
     frame.render_widget(paragraph, area);
+
+    let cursor_x = area.x + 3 + app.command_line.input.chars().count() as u16;
+    frame.set_cursor_position(Position::new(cursor_x.min(area.right().saturating_sub(2)), area.y + 1));
 }
 
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
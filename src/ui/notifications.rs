@@ -0,0 +1,100 @@
+use ratatui::{layout::Rect, style::Style, symbols::border, text::{Line, Span}, widgets::{Block, Clear, Padding, Paragraph, Wrap}, Frame};
+
+use crate::{app::App, notifications::{Severity, ToastPosition}, ui::centered_rect};
+
+const TOAST_WIDTH: u16 = 44;
+
+fn severity_style(app: &App, severity: Severity) -> Style{
+    match severity{
+        Severity::Info => app.theme.info,
+        Severity::Success => app.theme.success,
+        Severity::Warning => app.theme.warning,
+        Severity::Error => app.theme.error,
+    }
+}
+
+/// Lines message takes when wrapped to `width`
+fn wrapped_height(message: &str, width: u16) -> u16{
+    let width = width.max(1) as usize;
+    message.lines()
+        .map(|line| line.chars().count().max(1).div_ceil(width) as u16)
+        .sum::<u16>()
+        .max(1)
+}
+
+/// Stacks visible toasts from configured corner of `area`
+pub fn render_toasts(app: &mut App, frame: &mut Frame, area: Rect){
+    let config = &app.app_config.notifications;
+    let width = TOAST_WIDTH.min(area.width);
+    let x = match config.position{
+        ToastPosition::TopLeft | ToastPosition::BottomLeft => area.x,
+        ToastPosition::TopRight | ToastPosition::BottomRight => area.x + area.width - width,
+    };
+    let from_top = matches!(config.position, ToastPosition::TopLeft | ToastPosition::TopRight);
+
+    let waiting = app.notifications.waiting(config);
+    let mut used: u16 = 0;
+    for (id, toast) in app.notifications.visible(config).enumerate(){
+        let mut title = format!(" {} ", toast.severity);
+        if id == 0 && waiting > 0{
+            title = format!(" {} (+{} more) ", toast.severity, waiting);
+        }
+        let height = wrapped_height(&toast.message, width.saturating_sub(4)) + 2;
+        if used + height > area.height{
+            break;
+        }
+        let y = if from_top{
+            area.y + used
+        }else{
+            area.y + area.height - used - height
+        };
+        used += height;
+
+        let block = Block::bordered()
+            .title(title)
+            .border_set(border::ROUNDED)
+            .padding(Padding::horizontal(1));
+
+        let paragraph = Paragraph::new(toast.message.clone())
+            .block(block)
+            .wrap(Wrap { trim: true })
+            .style(severity_style(app, toast.severity));
+
+        let toast_area = Rect::new(x, y, width, height);
+        frame.render_widget(Clear, toast_area);
+        frame.render_widget(paragraph, toast_area);
+    }
+}
+
+pub fn render_notification_history(app: &mut App, frame: &mut Frame, area: Rect){
+    let mut lines: Vec<Line> = app.notifications.history()
+        .skip(app.notifications.history_scroll)
+        .map(|notification| Line::from(vec![
+            Span::styled(format!("{:>8} ", notification.severity), severity_style(app, notification.severity)),
+            Span::raw(" "),
+            Span::raw(notification.message.replace('\n', " ")),
+        ]))
+        .collect();
+    if lines.is_empty(){
+        lines.push(Line::styled("No notifications yet", app.theme.help_text));
+    }
+
+    let block = Block::bordered()
+        .title(format!(" notifications ({}) ", app.notifications.history_len()))
+        .title_bottom(Line::from(vec![
+            Span::styled(" Up/Down ", app.theme.help_key),
+            Span::styled("scroll ", app.theme.help_text),
+            Span::styled(" Esc ", app.theme.help_key),
+            Span::styled("close ", app.theme.help_text),
+        ]))
+        .border_set(border::ROUNDED)
+        .padding(Padding::horizontal(1));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .style(app.theme.floating);
+
+    let area = centered_rect(70, 60, area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
+}
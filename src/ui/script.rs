@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+use ratatui::{layout::Rect, style::Stylize, symbols::border, text::Line, widgets::{Block, Clear, Padding, Paragraph, Wrap}, Frame};
+
+use crate::{app::App, theme::StyleData, traits::tr_widget::TrWidget, ui::{centered_rect, widgets::{ConstraintFit, WidgetData}}};
+
+pub struct ScriptWidget{
+    pub function: String,
+}
+
+impl ScriptWidget{
+    pub fn new(function: String) -> Self{
+        Self { function }
+    }
+}
+
+impl TrWidget for ScriptWidget{
+    fn render(&self, frame: &mut Frame, app: &App, layout_data: &HashMap<String, Vec<Rect>>, data: &WidgetData) {
+        let area = match data.constraint_fit{
+            ConstraintFit::Default => layout_data.get(&data.layout).expect("no layout with provided id").get(data.constraint).expect("no constraint with provided id"),
+            ConstraintFit::Centered { percent_x, percent_y } => &centered_rect(percent_x, percent_y, *layout_data.get(&data.layout).expect("no layout with provided id").get(data.constraint).expect("no constraint with provided id"))
+        };
+
+        let line_vec: Vec<Line> = app.script_output.get(&self.function)
+            .map(|output| output.lines().map(|line| Line::from(line.to_string())).collect())
+            .unwrap_or_default();
+
+        let style_data: &StyleData = if let Some(data) = data.styles.get(&app.state){
+            app.app_config.styles.get(data).expect("No style with provided name")
+        }else if let Some(data) = data.styles.get("_"){
+            app.app_config.styles.get(data).expect("No default style provoded")
+        }else{
+            &StyleData::new()
+        };
+
+        let style = style_data.to_style();
+
+        let block = Block::bordered()
+            .title(Line::from(format!(" {} ", self.function).bold()))
+            .border_set(border::PLAIN)
+            .padding(Padding::new(2, 4, 1, 1))
+            .style(style);
+
+        let paragraph = Paragraph::new(line_vec).block(block).wrap(Wrap { trim: true }).style(style);
+        frame.render_widget(Clear, *area);
+        frame.render_widget(paragraph, *area);
+    }
+}
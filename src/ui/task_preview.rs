@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use ratatui::{layout::Rect, style::Stylize, symbols::border, text::Line, widgets::{Block, Clear, Padding, Paragraph, Wrap}, Frame};
+
+use crate::{app::App, json_types::{ExpSource, TaskStatus}, schedule::format_timestamp, theme::StyleData, traits::tr_widget::TrWidget, ui::{centered_rect, widgets::{ConstraintFit, WidgetData}}};
+
+pub struct TaskPreviewWidget{}
+
+impl TaskPreviewWidget{
+    pub fn new() -> Self{Self {}}
+}
+
+impl TrWidget for TaskPreviewWidget{
+    fn render(&self, frame: &mut Frame, app: &App, layout_data: &HashMap<String, Vec<Rect>>, data: &WidgetData) {
+        let area = match data.constraint_fit{
+            ConstraintFit::Default => layout_data.get(&data.layout).expect("no layout with provided id").get(data.constraint).expect("no constraint with provided id"),
+            ConstraintFit::Centered { percent_x, percent_y } => &centered_rect(percent_x, percent_y, *layout_data.get(&data.layout).expect("no layout with provided id").get(data.constraint).expect("no constraint with provided id"))
+        };
+
+        let mut line_vec: Vec<Line> = Vec::new();
+
+        if let Some((category_id, task_id)) = app.selected_task() && let Some(category) = app.data.get_category(category_id) && let Some(task) = category.get_task(task_id){
+            // Matched by uid, so completions stay counted after the task is renamed
+            let completions = app.data.category_history(category.get_uid())
+                .filter(|event| matches!(event.source, ExpSource::Task { uid, .. } if uid == task.get_uid()))
+                .count();
+
+            let status = match task.status(){
+                TaskStatus::Repeatable => "repeatable",
+                TaskStatus::Due => "due",
+                TaskStatus::Done => "done",
+            };
+            let last_completed = match task.last_completed{
+                Some(last_completed) => format_timestamp(last_completed),
+                None => "never".to_string(),
+            };
+
+            line_vec.push(Line::from(format!("{} (#{})", task.name, task.get_uid()).bold()));
+            line_vec.push(Line::from(""));
+            line_vec.push(Line::from(format!("Category: {}", category.name)));
+            line_vec.push(Line::from(format!("Exp reward: {}", task.exp_reward)));
+            line_vec.push(Line::from(format!("Repeats: {}", task.recurrence)));
+            line_vec.push(Line::from(format!("Status: {}", status)));
+            line_vec.push(Line::from(format!("Streak: {} (best {})", task.streak, task.best_streak)));
+            line_vec.push(Line::from(format!("Completed: {} times, last {}", completions, last_completed)));
+        }
+        else{
+            line_vec.push(Line::from("No task selected"));
+        }
+
+        let style_data: &StyleData = if let Some(data) = data.styles.get(&app.state){
+            app.app_config.styles.get(data).expect("No style with provided name")
+        }else if let Some(data) = data.styles.get("_"){
+            app.app_config.styles.get(data).expect("No default style provoded")
+        }else{
+            &StyleData::new()
+        };
+
+        let style = style_data.to_style();
+
+        let block = Block::bordered()
+            .title(Line::from(" Task ".bold()))
+            .border_set(border::PLAIN)
+            .padding(Padding::new(2, 4, 1, 1))
+            .style(style);
+
+        let paragraph = Paragraph::new(line_vec).block(block).wrap(Wrap { trim: true }).style(style);
+        frame.render_widget(Clear, *area);
+        frame.render_widget(paragraph, *area);
+    }
+}
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use ratatui::{layout::Rect, style::Stylize, symbols::border, text::{Line, Span}, widgets::{Block, List, ListItem, Padding}};
 
-use crate::{theme::StyleData, traits::tr_widget::TrWidget, ui::{centered_rect, widgets::{variant_id_to_usize, ConstraintFit, WidgetData}}};
+use crate::{json_types::TaskStatus, theme::StyleData, traits::tr_widget::TrWidget, ui::{centered_rect, widgets::{variant_id_to_usize, ConstraintFit, WidgetData}}};
 
 pub struct TaskWidget{}
 
@@ -37,7 +37,14 @@ impl TrWidget for TaskWidget{
 
                 if let Some(task_id) = task_id{
                     for (i, task) in category.tasks.iter().enumerate(){
-                        let task_text = format!("{} [+{} XP]", task.name, task.exp_reward);
+                        let mut task_text = match task.status(){
+                            TaskStatus::Repeatable => format!("{} [+{} XP]", task.name, task.exp_reward),
+                            TaskStatus::Due => format!("[ ] {} [+{} XP] {}", task.name, task.exp_reward, task.recurrence),
+                            TaskStatus::Done => format!("[x] {} [+{} XP] {}", task.name, task.exp_reward, task.recurrence),
+                        };
+                        if task.best_streak > 0{
+                            task_text.push_str(&format!(" | streak {} (best {})", task.streak, task.best_streak));
+                        }
 
                         let style = if focus {
                             if i == task_id{
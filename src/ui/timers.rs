@@ -35,11 +35,23 @@ impl TrWidget for TimerWidget{
 
         if let Some(timer_id) = timer_id{
             for (i, timer) in app.timers.iter().enumerate(){
-                let label_left = format!("{}", timer.category_name);
-                let label_right = String::new();
-                let ratio = (timer.get_second()%app.app_config.timer_frequency) / app.app_config.timer_frequency;
+                let mut label_left = format!("{}", timer.category_name);
+                let mut label_right = if timer.is_paused() { "paused".to_string() } else { String::new() };
+                let mut ratio = (timer.get_second()%app.app_config.timer_frequency) / app.app_config.timer_frequency;
+                // Pomodoro gauge shows progress of current phase instead of exp interval
+                let pomodoro = &app.app_config.pomodoro;
+                if let Some((phase, work_number, left)) = timer.pomodoro_phase(pomodoro){
+                    label_left = format!("{} {} {}/{}", timer.category_name, phase, work_number, pomodoro.cycle_count);
+                    let left = left.ceil() as u64;
+                    label_right = format!("{:02}:{:02} {}", left / 60, left % 60, label_right).trim_end().to_string();
+                    let length = pomodoro.phase_seconds(phase);
+                    ratio = if length > 0.0 { (1.0 - left as f32 / length).max(0.0) } else { 0.0 };
+                }
 
-                let state = if focus{
+                // Paused timers always look faded, so they stand out from running ones
+                let state = if timer.is_paused(){
+                    if focus && i == timer_id { GaugeState::FadedFocus } else { GaugeState::FadedPassive }
+                }else if focus{
                     if i == timer_id{
                         GaugeState::Focus
                     }
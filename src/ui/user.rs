@@ -1,7 +1,7 @@
 use num_format::{Buffer, Locale};
 use ratatui::{layout::{self, Constraint, Layout}, text::{Line, Span}, widgets::{Clear, Paragraph}};
 
-use crate::{json_types::calculate_exp, theme::{GaugeState, StyleData}, traits::tr_widget::TrWidget, ui::{centered_rect, gauge::build_gauge, widgets::ConstraintFit}};
+use crate::{exp_curve::ExpCurve, theme::{GaugeState, StyleData}, traits::tr_widget::TrWidget, ui::{centered_rect, gauge::build_gauge, widgets::ConstraintFit}};
 
 pub struct UserWidget{
     name: String,
@@ -13,19 +13,8 @@ impl UserWidget{
     }
 }
 
-pub fn get_stats(global_exp: u64, base: u32, power: f32) -> (u32, u64, u64){
-    let mut lvl: u32 = 1;
-    let mut required_exp = base as u64;
-    let mut cur_exp = global_exp;
-    
-
-    while cur_exp >= required_exp{
-        lvl += 1;
-        cur_exp -= required_exp;
-        required_exp = calculate_exp(lvl, power, base) as u64;
-    }
-
-    (lvl, cur_exp, required_exp)
+pub fn get_stats(global_exp: u64, curve: &ExpCurve) -> (u32, u64, u64){
+    curve.stats(global_exp)
 }
 
 impl TrWidget for UserWidget{
@@ -50,7 +39,7 @@ impl TrWidget for UserWidget{
 
         let style = style_data.to_style();
         
-        let (lvl, cur_exp, required_exp) = get_stats(global_exp, app.app_config.base_exp, app.app_config.exp_power);
+        let (lvl, cur_exp, required_exp) = get_stats(global_exp, &app.app_config.exp_curve());
         let ratio = cur_exp as f32 / required_exp as f32;
 
         let layout = Layout::default()
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{traits::tr_widget::TrWidget, ui::{categories::CategoriesWidget, help::HelpWidget, input::InputWidget, milestones::MilestoneWidget, tasks::TaskWidget, timers::TimerWidget, user::UserWidget}, wild_type::Variant};
+use crate::{traits::tr_widget::TrWidget, ui::{categories::CategoriesWidget, category_preview::CategoryPreviewWidget, milestone_preview::MilestonePreviewWidget, script::ScriptWidget, task_preview::TaskPreviewWidget, help::HelpWidget, input::InputWidget, milestones::MilestoneWidget, tasks::TaskWidget, timers::TimerWidget, user::UserWidget}, wild_type::Variant};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum WidgetTypes{
@@ -13,6 +13,11 @@ pub enum WidgetTypes{
     Help,
     Input {buffer_name: String},
     User {name: String},
+    CategoryPreview,
+    TaskPreview,
+    MilestonePreview,
+    /// Shows value returned by script function
+    Script {function: String},
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -31,6 +36,10 @@ impl WidgetTypes{
             WidgetTypes::Input {buffer_name} => Some(Box::new(InputWidget::new(buffer_name.to_string()))),
             WidgetTypes::Timers => Some(Box::new(TimerWidget::new())),
             WidgetTypes::User { name } => Some(Box::new(UserWidget::new(name.to_string()))),
+            WidgetTypes::CategoryPreview => Some(Box::new(CategoryPreviewWidget::new())),
+            WidgetTypes::TaskPreview => Some(Box::new(TaskPreviewWidget::new())),
+            WidgetTypes::MilestonePreview => Some(Box::new(MilestonePreviewWidget::new())),
+            WidgetTypes::Script { function } => Some(Box::new(ScriptWidget::new(function.to_string()))),
             _ => None
         }
     }
@@ -0,0 +1,269 @@
+use crate::{exp_curve::ExpCurve, json_types::{Category, Data, ExpEvent}, timer::Timer};
+
+/// Category as it was before and after a command, None when it didn't exist
+struct CategoryChange{
+    uid: u64,
+    before: Option<Category>,
+    after: Option<Category>,
+}
+
+/// Data as it was before a command, without copying history which commands only append to
+pub struct Snapshot{
+    categories: Vec<Category>,
+    fingerprint: String,
+    /// Whole history, kept only for commands replacing it
+    history: Option<Vec<ExpEvent>>,
+    history_len: usize,
+    last_event: Option<ExpEvent>,
+}
+
+impl Snapshot{
+    pub fn take(data: &Data, whole_history: bool) -> Self{
+        Self {
+            categories: data.categories.clone(),
+            fingerprint: data.curve_fingerprint.clone(),
+            history: whole_history.then(|| data.history.clone()),
+            history_len: data.history.len(),
+            last_event: data.history.last().cloned(),
+        }
+    }
+
+    /// False when history was replaced without being kept, so the change can't be told
+    pub fn history_kept(&self, data: &Data) -> bool{
+        self.history.is_some() || (self.history_len <= data.history.len() && data.history[..self.history_len].last() == self.last_event.as_ref())
+    }
+}
+
+/// Difference a command made to data, limited to categories it touched
+pub struct DataChange{
+    categories: Vec<CategoryChange>,
+    order_before: Vec<u64>,
+    order_after: Vec<u64>,
+    /// History events the command removed and added
+    history_before: Vec<ExpEvent>,
+    history_after: Vec<ExpEvent>,
+    fingerprint_before: String,
+    fingerprint_after: String,
+    /// Running timers of categories the change removed, given back when they exist again
+    pub timers: Vec<Timer>,
+}
+
+impl DataChange{
+    /// Change between data before and after a command, None when nothing changed
+    pub fn between(before: &Snapshot, after: &Data) -> Option<Self>{
+        let mut categories: Vec<CategoryChange> = Vec::new();
+        for category in before.categories.iter(){
+            let uid = category.get_uid();
+            let changed = after.categories.iter().find(|other| other.get_uid() == uid);
+            if changed != Some(category){
+                categories.push(CategoryChange { uid, before: Some(category.clone()), after: changed.cloned() });
+            }
+        }
+        for category in after.categories.iter(){
+            let uid = category.get_uid();
+            if !before.categories.iter().any(|other| other.get_uid() == uid){
+                categories.push(CategoryChange { uid, before: None, after: Some(category.clone()) });
+            }
+        }
+
+        let (history_before, history_after) = match &before.history{
+            Some(history) => (missing_events(history, &after.history), missing_events(&after.history, history)),
+            None => (Vec::new(), after.history.get(before.history_len..).unwrap_or_default().to_vec()),
+        };
+        let change = Self {
+            categories,
+            order_before: before.categories.iter().map(Category::get_uid).collect(),
+            order_after: after.categories.iter().map(Category::get_uid).collect(),
+            history_before,
+            history_after,
+            fingerprint_before: before.fingerprint.clone(),
+            fingerprint_after: after.curve_fingerprint.clone(),
+            timers: Vec::new(),
+        };
+        let unchanged = change.categories.is_empty() && change.order_before == change.order_after
+            && change.history_before.is_empty() && change.history_after.is_empty()
+            && change.fingerprint_before == change.fingerprint_after;
+        (!unchanged).then_some(change)
+    }
+
+    pub fn undo(&mut self, data: &mut Data, timers: &mut Vec<Timer>, curve: &ExpCurve){
+        self.apply(data, false, curve);
+        self.swap_timers(data, timers);
+    }
+
+    pub fn redo(&mut self, data: &mut Data, timers: &mut Vec<Timer>, curve: &ExpCurve){
+        self.apply(data, true, curve);
+        self.swap_timers(data, timers);
+    }
+
+    /// Moves data from one side of the change to the other, keeping what happened since in the background
+    fn apply(&self, data: &mut Data, forward: bool, curve: &ExpCurve){
+        for change in self.categories.iter(){
+            let (from, to) = if forward { (&change.before, &change.after) } else { (&change.after, &change.before) };
+            let position = data.categories.iter().position(|category| category.get_uid() == change.uid);
+            match (position, from, to){
+                (Some(position), Some(from), Some(to)) => {
+                    data.categories[position] = merge_category(&data.categories[position], from, to, curve);
+                }
+                (Some(position), _, None) => {
+                    data.categories.remove(position);
+                }
+                (None, _, Some(to)) => {
+                    data.categories.push(to.clone());
+                }
+                _ => {}
+            }
+        }
+
+        let order = if forward { &self.order_after } else { &self.order_before };
+        // Categories missing from the order keep their place after the ordered ones
+        data.categories.sort_by_key(|category| order.iter().position(|uid| *uid == category.get_uid()).unwrap_or(usize::MAX));
+
+        let (removed, added) = if forward { (&self.history_before, &self.history_after) } else { (&self.history_after, &self.history_before) };
+        for event in removed.iter(){
+            if let Some(position) = data.history.iter().rposition(|other| other == event){
+                data.history.remove(position);
+            }
+        }
+        for event in added.iter(){
+            let position = data.history.partition_point(|other| other.timestamp <= event.timestamp);
+            data.history.insert(position, event.clone());
+        }
+
+        if self.fingerprint_before != self.fingerprint_after{
+            data.curve_fingerprint = if forward { self.fingerprint_after.clone() } else { self.fingerprint_before.clone() };
+        }
+    }
+
+    /// Takes timers whose category is gone and gives back kept ones whose category exists again
+    pub fn swap_timers(&mut self, data: &Data, timers: &mut Vec<Timer>){
+        let exists = |timer: &Timer| data.categories.iter().any(|category| category.get_uid() == timer.category_id);
+        let (kept, returned): (Vec<Timer>, Vec<Timer>) = std::mem::take(&mut self.timers).into_iter().partition(|timer| !exists(timer));
+        let (running, removed): (Vec<Timer>, Vec<Timer>) = std::mem::take(timers).into_iter().partition(|timer| exists(timer));
+        *timers = running;
+        timers.extend(returned);
+        self.timers = kept;
+        self.timers.extend(removed);
+    }
+}
+
+/// Events of `events` which `other` doesn't have, counting repeated ones
+fn missing_events(events: &[ExpEvent], other: &[ExpEvent]) -> Vec<ExpEvent>{
+    let mut other: Vec<&ExpEvent> = other.iter().collect();
+    let mut missing: Vec<ExpEvent> = Vec::new();
+    for event in events.iter(){
+        match other.iter().position(|candidate| *candidate == event){
+            Some(position) => { other.remove(position); }
+            None => missing.push(event.clone()),
+        }
+    }
+    missing
+}
+
+/// `to` with exp and streaks `current` gained since `from`, so timers and streak checks aren't rolled back
+fn merge_category(current: &Category, from: &Category, to: &Category, curve: &ExpCurve) -> Category{
+    let mut merged = to.clone();
+    if current.exp_sum > from.exp_sum{
+        merged.increase_exp((current.exp_sum - from.exp_sum) as u32, curve);
+    }else if current.exp_sum < from.exp_sum{
+        merged.exp_sum = merged.exp_sum.saturating_sub(from.exp_sum - current.exp_sum);
+        merged.recalculate(curve);
+    }
+    if (current.streak, current.best_streak, current.last_active) != (from.streak, from.best_streak, from.last_active){
+        merged.streak = current.streak;
+        merged.best_streak = current.best_streak;
+        merged.last_active = current.last_active;
+    }
+    for task in merged.tasks.iter_mut(){
+        let uid = task.get_uid();
+        let current_task = current.tasks.iter().find(|other| other.get_uid() == uid);
+        let from_task = from.tasks.iter().find(|other| other.get_uid() == uid);
+        if let (Some(current_task), Some(from_task)) = (current_task, from_task) && current_task.streak != from_task.streak{
+            task.streak = current_task.streak;
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::{app::{App, AppCommands}, json_types::ExpSource, wild_type::Variant};
+
+    /// App with category `A` holding task `T` worth 5 exp, both selected
+    fn app_with_task() -> App{
+        let mut app = App::new();
+        app.run_command(&AppCommands::AddCategory("A".to_string()));
+        app.additional_data.insert("category_id".to_string(), Variant::Int(0));
+        app.run_command(&AppCommands::AddTask("T".to_string(), "5".to_string()));
+        app.additional_data.insert("task_id".to_string(), Variant::Int(0));
+        app
+    }
+
+    fn task_completed(app: &App) -> bool{
+        app.data.categories[0].tasks[0].last_completed.is_some()
+    }
+
+    #[test]
+    fn undoes_and_redoes_completed_task(){
+        let mut app = app_with_task();
+        app.run_command(&AppCommands::CompleteTask);
+        assert_eq!((app.data.categories[0].exp_sum, app.data.history.len()), (5, 1));
+        assert!(task_completed(&app));
+
+        app.undo();
+        assert_eq!((app.data.categories[0].exp_sum, app.data.history.len()), (0, 0));
+        assert!(!task_completed(&app));
+        assert_eq!(app.data.categories[0].tasks[0].name, "T");
+
+        app.redo();
+        assert_eq!((app.data.categories[0].exp_sum, app.data.history.len()), (5, 1));
+        assert!(task_completed(&app));
+    }
+
+    #[test]
+    fn keeps_exp_accrued_after_the_command(){
+        let mut app = app_with_task();
+        let uid = app.data.categories[0].get_uid();
+        app.run_command(&AppCommands::CompleteTask);
+        app.give_exp(uid, 3, ExpSource::Timer { seconds: 45 });
+        assert_eq!(app.data.categories[0].exp_sum, 8);
+
+        app.undo();
+        assert_eq!(app.data.categories[0].exp_sum, 3);
+        assert_eq!(app.data.history.len(), 1);
+        assert!(matches!(app.data.history[0].source, ExpSource::Timer { seconds: 45 }));
+
+        app.redo();
+        assert_eq!(app.data.categories[0].exp_sum, 8);
+        assert_eq!(app.data.history.len(), 2);
+        assert!(app.data.history.windows(2).all(|events| events[0].timestamp <= events[1].timestamp));
+    }
+
+    #[test]
+    fn removed_category_gets_its_timer_back(){
+        let mut app = app_with_task();
+        let uid = app.data.categories[0].get_uid();
+        app.run_command(&AppCommands::Timer);
+        app.run_command(&AppCommands::RemoveCategory);
+        assert!(app.data.categories.is_empty());
+        assert!(app.timers.is_empty());
+
+        app.undo();
+        assert_eq!(app.data.categories[0].get_uid(), uid);
+        assert_eq!(app.data.categories[0].tasks.len(), 1);
+        assert_eq!(app.timers.len(), 1);
+        assert_eq!(app.timers[0].category_id, uid);
+
+        app.redo();
+        assert!(app.data.categories.is_empty());
+        assert!(app.timers.is_empty());
+    }
+
+    #[test]
+    fn commands_changing_nothing_leave_no_entry(){
+        let mut app = app_with_task();
+        app.run_command(&AppCommands::AddTask("T".to_string(), "5".to_string()));
+        app.undo();
+        assert!(app.data.categories[0].tasks.is_empty());
+    }
+}
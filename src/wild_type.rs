@@ -1,4 +1,6 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
+
+use crate::{app::App, script::ast::Node};
 
 #[derive(Clone, Debug)]
 pub enum Variant{
@@ -64,6 +66,58 @@ impl Variant{
     }
 }
 
+impl Variant{
+    pub fn type_name(&self) -> &'static str{
+        match self{
+            Variant::Int(_) => "int",
+            Variant::Float(_) => "float",
+            Variant::Bool(_) => "bool",
+            Variant::Str(_) => "string",
+            Variant::Array(_, _) => "array",
+            Variant::Dictionary(_, _) => "dictionary",
+            Variant::Function() => "function",
+            Variant::UserType(_) => "object",
+            Variant::NULL => "null",
+        }
+    }
+    /// False for null, false, zero and empty strings or arrays
+    pub fn is_truthy(&self) -> bool{
+        match self{
+            Variant::Int(value) => *value != 0,
+            Variant::Float(value) => *value != 0.0,
+            Variant::Bool(value) => *value,
+            Variant::Str(value) => !value.is_empty(),
+            Variant::Array(values, _) => !values.is_empty(),
+            Variant::Dictionary(values, _) => !values.is_empty(),
+            Variant::Function() | Variant::UserType(_) => true,
+            Variant::NULL => false,
+        }
+    }
+}
+
+impl fmt::Display for Variant{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            Variant::Int(value) => write!(f, "{}", value),
+            Variant::Float(value) => write!(f, "{}", value),
+            Variant::Bool(value) => write!(f, "{}", value),
+            Variant::Str(value) => write!(f, "{}", value),
+            Variant::Array(values, _) => {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+            Variant::Dictionary(values, _) => {
+                let mut values: Vec<String> = values.iter().map(|(key, value)| format!("{}: {}", key, value)).collect();
+                values.sort();
+                write!(f, "{{{}}}", values.join(", "))
+            }
+            Variant::Function() => write!(f, "function"),
+            Variant::UserType(user_type) => write!(f, "{}", user_type.borrow().name),
+            Variant::NULL => write!(f, "null"),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct UserType{
     pub name: String,
@@ -82,6 +136,19 @@ pub enum Generic{
 }
 
 impl Generic{
+    /// Whether value can be stored in variable of this type, ints are accepted as floats
+    pub fn accepts(&self, value: &Variant) -> bool{
+        match (self, value){
+            (Generic::Any, _) | (_, Variant::NULL) => true,
+            (Generic::Int, Variant::Int(_)) => true,
+            (Generic::Float, Variant::Float(_) | Variant::Int(_)) => true,
+            (Generic::Bool, Variant::Bool(_)) => true,
+            (Generic::Str, Variant::Str(_)) => true,
+            (Generic::Array(generic), Variant::Array(values, _)) => values.iter().all(|value| generic.accepts(value)),
+            (Generic::Object(name), Variant::UserType(user_type)) => user_type.borrow().name == *name,
+            _ => false,
+        }
+    }
     pub fn from_string(value: &str) -> Self{
         return match value {
             "int" => Generic::Int,
@@ -101,14 +168,13 @@ pub struct Variable{
 }
 
 pub enum Function{
-    Native(fn(Vec<Variable>) -> Variable),
+    Native(fn(&mut App, Vec<Variable>) -> Result<Variable, String>),
     Defined(DefinedFunction)
 }
 
 pub struct DefinedFunction{
-    pub params: Vec<String>,
-    //Change to nodes, when will be created
-    pub body: String
+    pub params: Vec<(String, Generic)>,
+    pub body: Vec<Node>,
 }
 
 pub enum Privacy{